@@ -0,0 +1,350 @@
+use crate::layers::{Layer, LayerMask};
+use crate::physics::{PhysicsContext, QueryFilter};
+use rapier3d::geometry::{Collider, ColliderHandle, Ray};
+use rapier3d::na::{Point3, Vector3};
+
+pub struct CharacterConfig {
+    /// Top horizontal speed, in units per second.
+    pub max_speed: f32,
+    /// How quickly the character reaches `max_speed` while there's input.
+    pub acceleration: f32,
+    /// How quickly the character comes to a stop without input.
+    pub deceleration: f32,
+    /// Fraction of `acceleration` and `deceleration` available while airborne.
+    pub air_control: f32,
+    /// Upwards speed given by a jump.
+    pub jump_speed: f32,
+    /// Seconds after walking off a ledge during which a jump is still allowed.
+    pub coyote_time: f32,
+    /// Steepest walkable slope, in radians.
+    pub max_slope: f32,
+    /// How far below the character the ground is still considered to be under it.
+    pub ground_distance: f32,
+}
+
+impl Default for CharacterConfig {
+    fn default() -> Self {
+        Self {
+            max_speed: 6.,
+            acceleration: 40.,
+            deceleration: 30.,
+            air_control: 0.3,
+            jump_speed: 6.,
+            coyote_time: 0.1,
+            max_slope: std::f32::consts::FRAC_PI_4,
+            ground_distance: 0.1,
+        }
+    }
+}
+
+/// Drives a dynamic body by setting its velocity directly.
+///
+/// The body should have its rotations locked and no friction since the controller takes care of
+/// slowing it down. Up is always away from the gravity acting on the character, so it can walk
+/// around planetoids and through gravity zones.
+pub struct CharacterController {
+    pub config: CharacterConfig,
+    grounded: bool,
+    ground_collider: Option<ColliderHandle>,
+    // velocity of the moving platform under the character, zero on anything else
+    ground_velocity: Vector3<f32>,
+    time_since_grounded: f32,
+    jump_requested: bool,
+    // from a jump until the character stops moving up, so it can't land again straight away
+    // while the ground is still within `ground_distance`
+    jumping: bool,
+}
+
+impl CharacterController {
+    pub fn new(config: CharacterConfig) -> Self {
+        Self {
+            config,
+            grounded: false,
+            ground_collider: None,
+            ground_velocity: Vector3::zeros(),
+            time_since_grounded: f32::MAX,
+            jump_requested: false,
+            jumping: false,
+        }
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// The collider the character is standing on, if any.
+    #[allow(unused)]
+    pub fn ground_collider(&self) -> Option<ColliderHandle> {
+        self.ground_collider
+    }
+
+    /// Jumps on the next update if the character is, or just was, on the ground.
+    pub fn jump(&mut self) {
+        self.jump_requested = true;
+    }
+
+    /// Moves the character one tick of `dt` seconds towards `direction`.
+    ///
+    /// `direction` is in world space, only its part along the ground is used and it is clamped
+    /// to a length of one. Movement is relative to the platform the character is standing on,
+    /// if any, so it gets carried along.
+    pub fn update(
+        &mut self,
+        physics: &mut PhysicsContext,
+        handle: ColliderHandle,
+        direction: Vector3<f32>,
+        dt: f32,
+    ) {
+        let up = match physics.collider(handle) {
+            Some(collider) => up_at(physics, &collider.position().translation.vector.into()),
+            None => return,
+        };
+        let rising = match physics.rigid_body(handle) {
+            Some(body) => body.linvel().dot(&up) > 0.,
+            None => false,
+        };
+        self.jumping &= rising;
+        self.probe_ground(physics, handle, &up);
+        if self.grounded {
+            self.time_since_grounded = 0.;
+        } else {
+            self.time_since_grounded += dt;
+        }
+
+        let config = &self.config;
+        let body = match physics.rigid_body_mut(handle) {
+            Some(body) => body,
+            None => return,
+        };
+
+        let ground_velocity = self.ground_velocity;
+        let velocity = *body.linvel() - ground_velocity;
+        let mut vertical = velocity.dot(&up);
+        let horizontal = velocity - up * vertical;
+
+        let direction = direction - up * direction.dot(&up);
+        let direction = if direction.norm_squared() > 1. {
+            direction.normalize()
+        } else {
+            direction
+        };
+        let target = direction * config.max_speed;
+        let mut rate = if direction.norm_squared() > 0. {
+            config.acceleration
+        } else {
+            config.deceleration
+        };
+        if !self.grounded {
+            rate *= config.air_control;
+        }
+        let horizontal = approach(horizontal, target, rate * dt);
+
+        if self.jump_requested && self.time_since_grounded <= config.coyote_time {
+            vertical = config.jump_speed;
+            // one jump per ground contact
+            self.time_since_grounded = f32::MAX;
+            self.grounded = false;
+            self.jumping = true;
+        }
+        self.jump_requested = false;
+
+        body.set_linvel(horizontal + up * vertical + ground_velocity, true);
+    }
+
+    fn probe_ground(
+        &mut self,
+        physics: &PhysicsContext,
+        handle: ColliderHandle,
+        up: &Vector3<f32>,
+    ) {
+        self.grounded = false;
+        self.ground_collider = None;
+        self.ground_velocity = Vector3::zeros();
+        if self.jumping {
+            return;
+        }
+
+        let collider = match physics.collider(handle) {
+            Some(collider) => collider,
+            None => return,
+        };
+        let not_self = |other: ColliderHandle, other_collider: &Collider| {
+            other != handle && !other_collider.is_sensor()
+        };
+        let filter = QueryFilter::from(LayerMask::ALL.without(Layer::Trigger)).predicate(&not_self);
+
+        let down = -up;
+        let hit = physics.cast_shape(
+            collider.position(),
+            &down,
+            collider.shape(),
+            self.config.ground_distance,
+            filter,
+        );
+        let (ground, _toi) = match hit {
+            Some(hit) => hit,
+            None => return,
+        };
+
+        // the shape cast only says something is there, ask a ray for the slope under our feet.
+        // The steeper the slope the further below the center it is, so the ray reaches far
+        // enough for the steepest walkable one and missing means it's steeper than that, or
+        // only under an edge of the character.
+        let half_height = collider.compute_aabb().half_extents().dot(&up.abs());
+        let max_cos = self.config.max_slope.cos();
+        let ray = Ray::new(collider.position().translation.vector.into(), down);
+        let normal = match physics.cast_ray_and_get_normal(
+            &ray,
+            (half_height + self.config.ground_distance) / max_cos,
+            filter,
+        ) {
+            Some((_, intersection)) => intersection.normal,
+            None => return,
+        };
+
+        if normal.dot(up) >= max_cos {
+            self.grounded = true;
+            self.ground_collider = Some(ground);
+            self.ground_velocity = physics
+                .kinematic_velocity(ground)
+                .unwrap_or_else(Vector3::zeros);
+        }
+    }
+}
+
+// away from the gravity at `point`, world up where there's none
+fn up_at(physics: &PhysicsContext, point: &Point3<f32>) -> Vector3<f32> {
+    let gravity = physics.gravity_at(point);
+    let magnitude = gravity.norm();
+    if magnitude > f32::EPSILON {
+        -gravity / magnitude
+    } else {
+        Vector3::y()
+    }
+}
+
+fn approach(current: Vector3<f32>, target: Vector3<f32>, max_delta: f32) -> Vector3<f32> {
+    let delta = target - current;
+    let distance = delta.norm();
+    if distance <= max_delta || distance == 0. {
+        target
+    } else {
+        current + delta / distance * max_delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rapier3d::dynamics::RigidBodyBuilder;
+    use rapier3d::geometry::ColliderBuilder;
+
+    const DT: f32 = 1. / 60.;
+    const EPSILON: f32 = 1.0e-4;
+
+    // a character standing just above the middle of a box sloped by `slope` radians
+    fn world(slope: f32) -> (PhysicsContext, ColliderHandle, ColliderHandle) {
+        let mut physics = PhysicsContext::new(0., -9.81, 0.);
+        let ground = physics.add_body(
+            RigidBodyBuilder::new_static()
+                .rotation(Vector3::z() * slope)
+                .build(),
+            ColliderBuilder::cuboid(10., 0.5, 10.).build(),
+        );
+        // the box's top and the capsule's radius are half a unit each, its segment one
+        let height = 1. + 1.01 / slope.cos();
+        let character = physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .translation(0., height, 0.)
+                .lock_rotations()
+                .build(),
+            ColliderBuilder::capsule_y(1., 0.5).friction(0.).build(),
+        );
+        physics.step();
+        (physics, ground, character)
+    }
+
+    fn tick(
+        physics: &mut PhysicsContext,
+        controller: &mut CharacterController,
+        handle: ColliderHandle,
+    ) {
+        controller.update(physics, handle, Vector3::zeros(), DT);
+        physics.step();
+    }
+
+    fn vertical(physics: &PhysicsContext, handle: ColliderHandle) -> f32 {
+        physics.rigid_body(handle).unwrap().linvel().y
+    }
+
+    #[test]
+    fn jumps_off_the_ground() {
+        let (mut physics, _ground, character) = world(0.);
+        let mut controller = CharacterController::new(Default::default());
+        for _ in 0..5 {
+            tick(&mut physics, &mut controller, character);
+        }
+        assert!(controller.is_grounded());
+
+        controller.jump();
+        controller.update(&mut physics, character, Vector3::zeros(), DT);
+        assert!((vertical(&physics, character) - controller.config.jump_speed).abs() < EPSILON);
+    }
+
+    #[test]
+    fn cannot_jump_again_in_the_air() {
+        let (mut physics, _ground, character) = world(0.);
+        let mut controller = CharacterController::new(Default::default());
+        for _ in 0..5 {
+            tick(&mut physics, &mut controller, character);
+        }
+        controller.jump();
+        tick(&mut physics, &mut controller, character);
+
+        // the first few of these are still within `ground_distance` of the floor
+        for _ in 0..20 {
+            let before = vertical(&physics, character);
+            controller.jump();
+            controller.update(&mut physics, character, Vector3::zeros(), DT);
+            assert!(!controller.is_grounded());
+            assert!((vertical(&physics, character) - before).abs() < EPSILON);
+            physics.step();
+        }
+    }
+
+    #[test]
+    fn jumps_within_coyote_time() {
+        for (ticks, allowed) in [(1, true), (10, false)].iter() {
+            let (mut physics, ground, character) = world(0.);
+            let mut controller = CharacterController::new(Default::default());
+            for _ in 0..5 {
+                tick(&mut physics, &mut controller, character);
+            }
+            physics.remove_body(ground);
+            physics.step();
+            for _ in 0..*ticks {
+                tick(&mut physics, &mut controller, character);
+            }
+            assert!(!controller.is_grounded());
+
+            controller.jump();
+            controller.update(&mut physics, character, Vector3::zeros(), DT);
+            let jumped =
+                (vertical(&physics, character) - controller.config.jump_speed).abs() < EPSILON;
+            assert_eq!(jumped, *allowed, "after {} ticks", ticks);
+        }
+    }
+
+    #[test]
+    fn slopes_steeper_than_max_slope_are_not_ground() {
+        let mut controller = CharacterController::new(Default::default());
+        let (mut physics, ground, character) = world(0.5);
+        controller.update(&mut physics, character, Vector3::zeros(), DT);
+        assert!(controller.is_grounded());
+        assert_eq!(controller.ground_collider(), Some(ground));
+
+        let (mut physics, _ground, character) = world(1.);
+        controller.update(&mut physics, character, Vector3::zeros(), DT);
+        assert!(!controller.is_grounded());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::entity::EntityKind;
+use crate::physics::PhysicsContext;
+use crate::trimesh::TriMesh;
+use rapier3d::geometry::{Collider, Shape, AABB};
+use rapier3d::math::{Isometry, Point};
+use rapier3d::na::{Point3, Vector3};
+use solstice_2d::LineVertex;
+
+const LINE_WIDTH: f32 = 2.;
+const CIRCLE_SEGMENTS: usize = 16;
+const NORMAL_LENGTH: f32 = 0.5;
+
+const SHAPE_COLOR: [f32; 4] = [0., 1., 0., 1.];
+const TERRAIN_COLOR: [f32; 4] = [0., 0.6, 0., 0.5];
+const TRIGGER_COLOR: [f32; 4] = [1., 0., 1., 1.];
+const AABB_COLOR: [f32; 4] = [1., 1., 0., 0.5];
+const CONTACT_COLOR: [f32; 4] = [1., 0., 0., 1.];
+const RAY_COLOR: [f32; 4] = [0., 1., 1., 1.];
+
+/// Builds the polylines outlining everything in `physics`.
+///
+/// Every inner `Vec` is one polyline. `ray` is the start and end of the last brush ray.
+pub fn lines(
+    physics: &PhysicsContext,
+    ray: Option<&(Point3<f32>, Point3<f32>)>,
+) -> Vec<Vec<LineVertex>> {
+    let mut lines = vec![];
+
+    for (_handle, collider) in physics.iter_colliders() {
+        shape_lines(
+            &mut lines,
+            collider.position(),
+            collider.shape(),
+            collider_color(collider),
+        );
+        aabb_lines(&mut lines, &collider.compute_aabb(), AABB_COLOR);
+    }
+
+    for (point, normal) in physics.contacts() {
+        lines.push(vec![
+            vertex(&point, CONTACT_COLOR),
+            vertex(&(point + normal * NORMAL_LENGTH), CONTACT_COLOR),
+        ]);
+    }
+
+    if let Some((from, to)) = ray {
+        lines.push(vec![vertex(from, RAY_COLOR), vertex(to, RAY_COLOR)]);
+    }
+
+    lines
+}
+
+fn collider_color(collider: &Collider) -> [f32; 4] {
+    match EntityKind::from_user_data(collider.user_data) {
+        Some(EntityKind::Terrain) => TERRAIN_COLOR,
+        Some(EntityKind::Trigger) => TRIGGER_COLOR,
+        _ => SHAPE_COLOR,
+    }
+}
+
+fn vertex(point: &Point<f32>, color: [f32; 4]) -> LineVertex {
+    LineVertex {
+        position: [point.x, point.y, point.z],
+        width: LINE_WIDTH,
+        color,
+    }
+}
+
+fn shape_lines(
+    lines: &mut Vec<Vec<LineVertex>>,
+    position: &Isometry<f32>,
+    shape: &dyn Shape,
+    color: [f32; 4],
+) {
+    if let Some(trimesh) = shape.as_shape::<TriMesh>() {
+        for triangle in trimesh.triangles() {
+            lines.push(
+                [triangle.a, triangle.b, triangle.c, triangle.a]
+                    .iter()
+                    .map(|point| vertex(&(position * point), color))
+                    .collect(),
+            );
+        }
+    } else if let Some(ball) = shape.as_ball() {
+        sphere_lines(lines, position, &Point::origin(), ball.radius, color);
+    } else if let Some(capsule) = shape.as_capsule() {
+        let (a, b) = (capsule.segment.a, capsule.segment.b);
+        sphere_lines(lines, position, &a, capsule.radius, color);
+        sphere_lines(lines, position, &b, capsule.radius, color);
+        let axis = (b - a).try_normalize(f32::EPSILON).unwrap_or(Vector3::y());
+        let tangent = axis.cross(&Vector3::x()).try_normalize(f32::EPSILON);
+        let tangent = tangent.unwrap_or_else(|| axis.cross(&Vector3::z()).normalize());
+        let bitangent = axis.cross(&tangent);
+        for offset in [tangent, -tangent, bitangent, -bitangent].iter() {
+            let offset = offset * capsule.radius;
+            lines.push(vec![
+                vertex(&(position * (a + offset)), color),
+                vertex(&(position * (b + offset)), color),
+            ]);
+        }
+    } else if let Some(cuboid) = shape.as_cuboid() {
+        let aabb = AABB::new((-cuboid.half_extents).into(), cuboid.half_extents.into());
+        box_lines(lines, position, &aabb, color);
+    } else if let Some(compound) = shape.as_compound() {
+        for (part_position, part) in compound.shapes() {
+            shape_lines(lines, &(position * part_position), &**part, color);
+        }
+    } else if let Some(polyhedron) = shape.as_convex_polyhedron() {
+        let (vertices, indices) = polyhedron.to_trimesh();
+        for triangle in indices {
+            lines.push(
+                [triangle[0], triangle[1], triangle[2], triangle[0]]
+                    .iter()
+                    .map(|i| vertex(&(position * vertices[*i as usize]), color))
+                    .collect(),
+            );
+        }
+    }
+}
+
+fn sphere_lines(
+    lines: &mut Vec<Vec<LineVertex>>,
+    position: &Isometry<f32>,
+    center: &Point<f32>,
+    radius: f32,
+    color: [f32; 4],
+) {
+    let planes = [
+        (Vector3::x(), Vector3::y()),
+        (Vector3::y(), Vector3::z()),
+        (Vector3::z(), Vector3::x()),
+    ];
+    for (u, v) in planes.iter() {
+        lines.push(
+            (0..=CIRCLE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::PI * 2.;
+                    let point = center + (u * angle.cos() + v * angle.sin()) * radius;
+                    vertex(&(position * point), color)
+                })
+                .collect(),
+        );
+    }
+}
+
+fn aabb_lines(lines: &mut Vec<Vec<LineVertex>>, aabb: &AABB, color: [f32; 4]) {
+    box_lines(lines, &Isometry::identity(), aabb, color)
+}
+
+fn box_lines(
+    lines: &mut Vec<Vec<LineVertex>>,
+    position: &Isometry<f32>,
+    aabb: &AABB,
+    color: [f32; 4],
+) {
+    let (mins, maxs) = (aabb.mins, aabb.maxs);
+    let corner = |x: bool, y: bool, z: bool| {
+        let point = Point::new(
+            if x { maxs.x } else { mins.x },
+            if y { maxs.y } else { mins.y },
+            if z { maxs.z } else { mins.z },
+        );
+        vertex(&(position * point), color)
+    };
+
+    // bottom and top loops then the four vertical edges
+    for &y in [false, true].iter() {
+        lines.push(vec![
+            corner(false, y, false),
+            corner(true, y, false),
+            corner(true, y, true),
+            corner(false, y, true),
+            corner(false, y, false),
+        ]);
+    }
+    for &(x, z) in [(false, false), (true, false), (true, true), (false, true)].iter() {
+        lines.push(vec![corner(x, false, z), corner(x, true, z)]);
+    }
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// What a collider stands for in the game, stored in its user data.
+///
+/// The kind lives in the lowest byte so the rest of the user data is free for anything else.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EntityKind {
+    Terrain,
+    Player,
+    Ball,
+    Debris,
+    Trigger,
+    /// Anything spawned that isn't a ball.
+    Prop,
+    /// Bodies tools like the grab use behind the scenes.
+    Tool,
+}
+
+const KIND_MASK: u128 = 0xff;
+
+// zero is untagged and one was the ball's tag before kinds existed, so neither is used
+const TERRAIN: u8 = 0x10;
+const PLAYER: u8 = 0x11;
+const BALL: u8 = 0x12;
+const DEBRIS: u8 = 0x13;
+const TRIGGER: u8 = 0x14;
+const PROP: u8 = 0x15;
+const TOOL: u8 = 0x16;
+
+/// Set in a collider's user data, next to its kind, when it carves the terrain wherever it hits
+/// it hard enough.
+pub const DIGGER_FLAG: u128 = 1 << 8;
+
+impl EntityKind {
+    fn tag(self) -> u8 {
+        match self {
+            EntityKind::Terrain => TERRAIN,
+            EntityKind::Player => PLAYER,
+            EntityKind::Ball => BALL,
+            EntityKind::Debris => DEBRIS,
+            EntityKind::Trigger => TRIGGER,
+            EntityKind::Prop => PROP,
+            EntityKind::Tool => TOOL,
+        }
+    }
+
+    /// The user data to give a collider of this kind.
+    pub fn user_data(self) -> u128 {
+        self.tag() as u128
+    }
+
+    /// Reads the kind back out of a collider's user data. Untagged colliders have none.
+    pub fn from_user_data(user_data: u128) -> Option<Self> {
+        match (user_data & KIND_MASK) as u8 {
+            TERRAIN => Some(EntityKind::Terrain),
+            PLAYER => Some(EntityKind::Player),
+            BALL => Some(EntityKind::Ball),
+            DEBRIS => Some(EntityKind::Debris),
+            TRIGGER => Some(EntityKind::Trigger),
+            PROP => Some(EntityKind::Prop),
+            TOOL => Some(EntityKind::Tool),
+            _ => None,
+        }
+    }
+}
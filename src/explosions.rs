@@ -0,0 +1,30 @@
+use rapier3d::na::Point3;
+
+/// How far out bodies are still pushed, as a multiple of the crater's radius.
+const BLAST_RADIUS_SCALE: f32 = 2.;
+const DEFAULT_IMPULSE: f32 = 15.;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Explosion {
+    pub center: Point3<f32>,
+    /// Radius of the crater left in the terrain.
+    pub radius: f32,
+    /// Impulse given to a body right at the center, falling off to nothing at the edge of the
+    /// blast.
+    pub impulse: f32,
+}
+
+impl Explosion {
+    pub fn new(center: Point3<f32>, radius: f32) -> Self {
+        Self {
+            center,
+            radius,
+            impulse: DEFAULT_IMPULSE,
+        }
+    }
+
+    /// How far from the center bodies are pushed.
+    pub fn blast_radius(&self) -> f32 {
+        self.radius * BLAST_RADIUS_SCALE
+    }
+}
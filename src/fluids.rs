@@ -0,0 +1,101 @@
+use crate::physics::PhysicsContext;
+use crate::trimesh::TriMesh;
+use rapier3d::geometry::AABB;
+use rapier3d::math::Point;
+use rapier3d::na::Vector3;
+
+/// How many points along each axis of a body's AABB are tested against a fluid.
+const SAMPLES_PER_AXIS: usize = 3;
+
+pub enum FluidBounds {
+    Aabb(AABB),
+    /// The inside of a closed solid, such as a basin carved out of the terrain.
+    Solid(Box<TriMesh>),
+}
+
+impl FluidBounds {
+    pub fn from_csg(csg: &rscsg::dim3::Csg) -> Self {
+        FluidBounds::Solid(Box::new(TriMesh::new(PhysicsContext::csg_vertices(csg))))
+    }
+
+    fn aabb(&self) -> AABB {
+        match self {
+            FluidBounds::Aabb(aabb) => *aabb,
+            FluidBounds::Solid(trimesh) => *trimesh.local_aabb(),
+        }
+    }
+
+    fn contains(&self, point: &Point<f32>) -> bool {
+        match self {
+            FluidBounds::Aabb(aabb) => aabb.contains_local_point(point),
+            FluidBounds::Solid(trimesh) => trimesh.contains_point(point),
+        }
+    }
+}
+
+pub struct FluidVolume {
+    pub bounds: FluidBounds,
+    /// Height of the surface, nothing above it is submerged.
+    pub surface_y: f32,
+    pub density: f32,
+    /// Opposes linear velocity, scaled by mass and how much of a body is submerged.
+    pub linear_drag: f32,
+    /// Opposes angular velocity, scaled the same way.
+    pub angular_drag: f32,
+}
+
+impl FluidVolume {
+    pub fn new(bounds: FluidBounds, surface_y: f32) -> Self {
+        Self {
+            bounds,
+            surface_y,
+            density: 1.5,
+            linear_drag: 1.,
+            angular_drag: 0.5,
+        }
+    }
+
+    pub fn contains(&self, point: &Point<f32>) -> bool {
+        point.y <= self.surface_y && self.bounds.contains(point)
+    }
+
+    /// The corners of the surface, as the bounds' extents at the surface height.
+    pub fn surface(&self) -> [Point<f32>; 4] {
+        let aabb = self.bounds.aabb();
+        let y = self.surface_y.min(aabb.maxs.y);
+        [
+            Point::new(aabb.mins.x, y, aabb.mins.z),
+            Point::new(aabb.maxs.x, y, aabb.mins.z),
+            Point::new(aabb.maxs.x, y, aabb.maxs.z),
+            Point::new(aabb.mins.x, y, aabb.maxs.z),
+        ]
+    }
+
+    /// Roughly how much of `aabb` is submerged, from 0 to 1, and the center of the submerged
+    /// part. `None` if none of it is.
+    pub fn submerged(&self, aabb: &AABB) -> Option<(f32, Point<f32>)> {
+        let extents = aabb.extents();
+        let mut count = 0;
+        let mut sum = Vector3::zeros();
+        for i in 0..SAMPLES_PER_AXIS {
+            for j in 0..SAMPLES_PER_AXIS {
+                for k in 0..SAMPLES_PER_AXIS {
+                    let t =
+                        Vector3::new(i, j, k).map(|n| (n as f32 + 0.5) / SAMPLES_PER_AXIS as f32);
+                    let point = aabb.mins + extents.component_mul(&t);
+                    if self.contains(&point) {
+                        count += 1;
+                        sum += point.coords;
+                    }
+                }
+            }
+        }
+
+        if count == 0 {
+            None
+        } else {
+            let total = SAMPLES_PER_AXIS * SAMPLES_PER_AXIS * SAMPLES_PER_AXIS;
+            Some((count as f32 / total as f32, Point::from(sum / count as f32)))
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::entity::EntityKind;
+use crate::layers::{Layer, LayerMask};
+use crate::physics::{PhysicsContext, QueryFilter, SpringHandle};
+use rapier3d::dynamics::RigidBodyBuilder;
+use rapier3d::geometry::{Collider, ColliderBuilder, ColliderHandle, Ray};
+use rapier3d::math::Isometry;
+use rapier3d::na::Point3;
+
+const ANCHOR_RADIUS: f32 = 0.1;
+
+pub struct GrabConfig {
+    /// How hard a held body is pulled towards the cursor, per unit of its mass.
+    pub stiffness: f32,
+    /// How much a held body's wobbling around the cursor is damped, per unit of its mass.
+    pub damping: f32,
+    /// Closest and furthest a held body can be from the camera.
+    pub distance: (f32, f32),
+    /// How fast a held body is pulled in or pushed out along the cursor, in units per second.
+    pub depth_speed: f32,
+    /// Speed a held body is thrown at.
+    pub throw_speed: f32,
+}
+
+impl Default for GrabConfig {
+    fn default() -> Self {
+        Self {
+            stiffness: 150.,
+            damping: 15.,
+            distance: (2., 30.),
+            depth_speed: 8.,
+            throw_speed: 15.,
+        }
+    }
+}
+
+struct Grab {
+    // kinematic body following the cursor that the held body hangs off
+    anchor: ColliderHandle,
+    target: ColliderHandle,
+    spring: SpringHandle,
+    // how far along the cursor ray the anchor is kept
+    distance: f32,
+}
+
+/// Picks up dynamic bodies and drags them around after the cursor.
+pub struct GrabTool {
+    pub config: GrabConfig,
+    grab: Option<Grab>,
+    depth: f32,
+}
+
+impl GrabTool {
+    pub fn new(config: GrabConfig) -> Self {
+        Self {
+            config,
+            grab: None,
+            depth: 0.,
+        }
+    }
+
+    pub fn is_grabbing(&self) -> bool {
+        self.grab.is_some()
+    }
+
+    /// Which way the held body is moving along the cursor ray, towards the camera when
+    /// negative and away from it when positive.
+    pub fn set_depth_input(&mut self, depth: f32) {
+        self.depth = depth.clamp(-1., 1.);
+    }
+
+    /// Grabs the first dynamic body along `ray`, letting go of anything already held. Returns
+    /// whether anything was grabbed.
+    pub fn grab(&mut self, physics: &mut PhysicsContext, ray: &Ray) -> bool {
+        self.release(physics);
+
+        let is_dynamic =
+            |_handle: ColliderHandle, collider: &Collider| physics.is_dynamic(collider);
+        let layers = LayerMask::ALL
+            .without(Layer::Trigger)
+            .without(Layer::Player);
+        let filter = QueryFilter::from(layers).predicate(&is_dynamic);
+        let (target, toi) = match physics.cast_ray(ray, filter) {
+            Some(hit) => hit,
+            None => return false,
+        };
+        let point = ray.point_at(toi);
+        let local_point = match physics.rigid_body(target) {
+            Some(body) => body.position().inverse_transform_point(&point),
+            None => return false,
+        };
+
+        let body = RigidBodyBuilder::new_kinematic()
+            .translation(point.x, point.y, point.z)
+            .build();
+        // a tool so it touches nothing and throws, grabs and other queries look straight
+        // through it
+        let collider = ColliderBuilder::ball(ANCHOR_RADIUS)
+            .sensor(true)
+            .user_data(EntityKind::Tool.user_data())
+            .build();
+        let anchor = physics.add_body(body, collider);
+        let spring = match physics.add_spring_joint(
+            Some(anchor),
+            Point3::origin(),
+            Some(target),
+            local_point,
+            self.config.stiffness,
+            self.config.damping,
+        ) {
+            Some(spring) => spring,
+            None => {
+                physics.remove_body(anchor);
+                return false;
+            }
+        };
+
+        let (min, max) = self.config.distance;
+        self.grab = Some(Grab {
+            anchor,
+            target,
+            spring,
+            distance: toi.max(min).min(max),
+        });
+        true
+    }
+
+    /// Moves the anchor one tick of `dt` seconds along after the cursor, `ray`. Lets go if the
+    /// held body has gone away.
+    pub fn update(&mut self, physics: &mut PhysicsContext, ray: &Ray, dt: f32) {
+        let held = match self.grab.as_ref() {
+            Some(grab) => physics.collider(grab.target).is_some(),
+            None => return,
+        };
+        if !held {
+            self.release(physics);
+            return;
+        }
+
+        let (min, max) = self.config.distance;
+        let grab = match self.grab.as_mut() {
+            Some(grab) => grab,
+            None => return,
+        };
+        grab.distance = (grab.distance + self.depth * self.config.depth_speed * dt)
+            .max(min)
+            .min(max);
+        let position = ray.point_at(grab.distance);
+        if let Some(anchor) = physics.rigid_body_mut(grab.anchor) {
+            anchor.set_next_kinematic_position(Isometry::translation(
+                position.x, position.y, position.z,
+            ));
+        }
+    }
+
+    /// Lets go of the held body, which keeps whatever velocity it had.
+    pub fn release(&mut self, physics: &mut PhysicsContext) -> Option<ColliderHandle> {
+        let grab = self.grab.take()?;
+        physics.remove_spring_joint(grab.spring);
+        physics.remove_body(grab.anchor);
+        Some(grab.target)
+    }
+
+    /// Lets go of the held body and launches it along `ray`.
+    pub fn throw(&mut self, physics: &mut PhysicsContext, ray: &Ray) {
+        let target = match self.release(physics) {
+            Some(target) => target,
+            None => return,
+        };
+        if let Some(body) = physics.rigid_body_mut(target) {
+            body.set_linvel(ray.dir.normalize() * self.config.throw_speed, true);
+        }
+    }
+}
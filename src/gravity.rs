@@ -0,0 +1,172 @@
+use rapier3d::geometry::AABB;
+use rapier3d::math::Point;
+use rapier3d::na::Vector3;
+
+pub enum GravityField {
+    /// Pulls along `acceleration` everywhere inside the box.
+    Directional {
+        bounds: AABB,
+        acceleration: Vector3<f32>,
+    },
+    /// Pulls towards `center` with a constant `strength` from anywhere within `radius`.
+    Radial {
+        center: Point<f32>,
+        radius: f32,
+        strength: f32,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GravityMode {
+    /// Replaces global gravity. When several overrides overlap, the last one added wins.
+    Override,
+    /// Adds to whatever gravity is otherwise in effect.
+    #[allow(unused)]
+    Add,
+}
+
+pub struct GravityZone {
+    pub field: GravityField,
+    pub mode: GravityMode,
+}
+
+impl GravityZone {
+    /// The acceleration this zone applies at `point`, `None` outside of it.
+    pub fn acceleration_at(&self, point: &Point<f32>) -> Option<Vector3<f32>> {
+        match &self.field {
+            GravityField::Directional {
+                bounds,
+                acceleration,
+            } => {
+                if bounds.contains_local_point(point) {
+                    Some(*acceleration)
+                } else {
+                    None
+                }
+            }
+            GravityField::Radial {
+                center,
+                radius,
+                strength,
+            } => {
+                let delta = center - point;
+                let distance = delta.norm();
+                // nowhere to pull towards from the very center
+                if distance > *radius || distance <= f32::EPSILON {
+                    None
+                } else {
+                    Some(delta / distance * *strength)
+                }
+            }
+        }
+    }
+}
+
+/// The gravity felt at `point` once every zone is taken into account.
+pub fn gravity_at(
+    global: &Vector3<f32>,
+    zones: &[GravityZone],
+    point: &Point<f32>,
+) -> Vector3<f32> {
+    let mut base = *global;
+    let mut added = Vector3::zeros();
+    for zone in zones {
+        if let Some(acceleration) = zone.acceleration_at(point) {
+            match zone.mode {
+                GravityMode::Override => base = acceleration,
+                GravityMode::Add => added += acceleration,
+            }
+        }
+    }
+    base + added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1.0e-5;
+
+    fn global() -> Vector3<f32> {
+        Vector3::new(0., -9.81, 0.)
+    }
+
+    fn directional(acceleration: Vector3<f32>, mode: GravityMode) -> GravityZone {
+        GravityZone {
+            field: GravityField::Directional {
+                bounds: AABB::new(Point::new(-1., -1., -1.), Point::new(1., 1., 1.)),
+                acceleration,
+            },
+            mode,
+        }
+    }
+
+    fn radial(mode: GravityMode) -> GravityZone {
+        GravityZone {
+            field: GravityField::Radial {
+                center: Point::new(10., 0., 0.),
+                radius: 5.,
+                strength: 3.,
+            },
+            mode,
+        }
+    }
+
+    #[test]
+    fn no_zones_is_global() {
+        let point = Point::new(3., 4., 5.);
+        assert_eq!(gravity_at(&global(), &[], &point), global());
+    }
+
+    #[test]
+    fn directional_overrides_inside_its_bounds() {
+        let zones = [directional(Vector3::x(), GravityMode::Override)];
+        assert_eq!(
+            gravity_at(&global(), &zones, &Point::new(0.5, 0., 0.)),
+            Vector3::x()
+        );
+        assert_eq!(
+            gravity_at(&global(), &zones, &Point::new(2., 0., 0.)),
+            global()
+        );
+    }
+
+    #[test]
+    fn radial_pulls_towards_its_center() {
+        let zones = [radial(GravityMode::Override)];
+        let gravity = gravity_at(&global(), &zones, &Point::new(10., 4., 0.));
+        assert!((gravity - Vector3::new(0., -3., 0.)).norm() < EPSILON);
+        let gravity = gravity_at(&global(), &zones, &Point::new(8., 0., 0.));
+        assert!((gravity - Vector3::new(3., 0., 0.)).norm() < EPSILON);
+    }
+
+    #[test]
+    fn radial_ends_at_its_radius_and_center() {
+        let zones = [radial(GravityMode::Override)];
+        assert_eq!(
+            gravity_at(&global(), &zones, &Point::new(10., 6., 0.)),
+            global()
+        );
+        assert_eq!(
+            gravity_at(&global(), &zones, &Point::new(10., 0., 0.)),
+            global()
+        );
+    }
+
+    #[test]
+    fn added_zones_stack_on_the_last_override() {
+        let zones = [
+            directional(Vector3::x(), GravityMode::Override),
+            directional(Vector3::z(), GravityMode::Override),
+            directional(Vector3::y(), GravityMode::Add),
+            directional(Vector3::y(), GravityMode::Add),
+        ];
+        assert_eq!(
+            gravity_at(&global(), &zones, &Point::origin()),
+            Vector3::new(0., 2., 1.)
+        );
+        let zones = [directional(Vector3::y(), GravityMode::Add)];
+        let gravity = gravity_at(&global(), &zones, &Point::origin());
+        assert!((gravity - Vector3::new(0., -8.81, 0.)).norm() < EPSILON);
+    }
+}
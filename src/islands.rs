@@ -0,0 +1,207 @@
+use crate::trimesh::weld_vertices;
+use rapier3d::math::Point;
+use rscsg::dim3::{Csg, Polygon, Triangle, Vector, Vertex};
+
+/// Anything smaller than this along every axis is too small to be worth simulating, so it's left
+/// in the terrain instead.
+const MIN_ISLAND_EXTENT: f32 = 0.05;
+
+/// The pieces of a solid after splitting it along its connected components.
+pub struct Split {
+    /// Every piece touching the anchor plane merged back together.
+    pub anchored: Csg,
+    /// Every piece that no longer touches the anchor plane.
+    pub floating: Vec<Csg>,
+}
+
+/// Splits `csg` into its connected components.
+///
+/// Components with a vertex at or below `anchor_y` are considered attached to the ground, as are
+/// floating ones too small to simulate. Returns `None` if nothing is floating so the caller can
+/// keep using `csg` as is.
+pub fn split(csg: &Csg, anchor_y: f32) -> Option<Split> {
+    let triangles = csg.iter_triangles().collect::<Vec<_>>();
+    let vertices = triangles
+        .iter()
+        .flat_map(|triangle| {
+            triangle
+                .positions
+                .iter()
+                .map(|Vector(x, y, z)| Point::new(*x, *y, *z))
+        })
+        .collect::<Vec<_>>();
+    let (ids, num_vertices) = weld_vertices(&vertices);
+
+    let mut components = DisjointSet::new(num_vertices);
+    for triangle in ids.chunks_exact(3) {
+        components.union(triangle[0], triangle[1]);
+        components.union(triangle[0], triangle[2]);
+    }
+
+    let mut anchored = vec![false; num_vertices];
+    for (vertex, id) in vertices.iter().zip(ids.iter()) {
+        if vertex.y <= anchor_y {
+            anchored[components.find(*id) as usize] = true;
+        }
+    }
+
+    let mut grounded = vec![];
+    let mut islands = std::collections::BTreeMap::new();
+    for (triangle, ids) in triangles.into_iter().zip(ids.chunks_exact(3)) {
+        let root = components.find(ids[0]);
+        if anchored[root as usize] {
+            grounded.push(triangle);
+        } else {
+            islands.entry(root).or_insert_with(Vec::new).push(triangle);
+        }
+    }
+
+    let mut floating = vec![];
+    for triangles in islands.into_values() {
+        if extent(&triangles) > MIN_ISLAND_EXTENT {
+            floating.push(to_csg(triangles));
+        } else {
+            grounded.extend(triangles);
+        }
+    }
+
+    if floating.is_empty() {
+        return None;
+    }
+    Some(Split {
+        anchored: to_csg(grounded),
+        floating,
+    })
+}
+
+fn extent(triangles: &[Triangle]) -> f32 {
+    let mut mins = [f32::MAX; 3];
+    let mut maxs = [f32::MIN; 3];
+    for Vector(x, y, z) in triangles
+        .iter()
+        .flat_map(|triangle| triangle.positions.iter())
+    {
+        for (axis, value) in [*x, *y, *z].iter().enumerate() {
+            mins[axis] = mins[axis].min(*value);
+            maxs[axis] = maxs[axis].max(*value);
+        }
+    }
+    (0..3)
+        .map(|axis| maxs[axis] - mins[axis])
+        .fold(0., f32::max)
+}
+
+pub fn to_csg(triangles: Vec<Triangle>) -> Csg {
+    let polygons = triangles
+        .into_iter()
+        .map(|triangle| {
+            let Vector(nx, ny, nz) = triangle.normal;
+            let vertices = IntoIterator::into_iter(triangle.positions)
+                .map(|position| Vertex::new(position, Vector(nx, ny, nz)))
+                .collect();
+            Polygon::new(vertices)
+        })
+        .collect();
+    Csg::from_polygons(polygons)
+}
+
+struct DisjointSet {
+    parents: Vec<u32>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len as u32).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: u32) -> u32 {
+        while self.parents[i as usize] != i {
+            let grandparent = self.parents[self.parents[i as usize] as usize];
+            self.parents[i as usize] = grandparent;
+            i = grandparent;
+        }
+        i
+    }
+
+    fn union(&mut self, a: u32, b: u32) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[b as usize] = a;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANCHOR_Y: f32 = -0.9;
+
+    fn cube(size: f32, center: Vector) -> Vec<Triangle> {
+        Csg::cube(Vector(size, size, size), true)
+            .translate(center)
+            .iter_triangles()
+            .collect()
+    }
+
+    fn solid(cubes: Vec<Vec<Triangle>>) -> Csg {
+        to_csg(cubes.into_iter().flatten().collect())
+    }
+
+    fn count(csg: &Csg) -> usize {
+        csg.iter_triangles().count()
+    }
+
+    #[test]
+    fn nothing_floating() {
+        let csg = solid(vec![cube(2., Vector(0., 0., 0.))]);
+        assert!(split(&csg, ANCHOR_Y).is_none());
+    }
+
+    #[test]
+    fn splits_off_floating_pieces() {
+        let csg = solid(vec![
+            cube(2., Vector(0., 0., 0.)),
+            cube(1., Vector(0., 5., 0.)),
+            cube(1., Vector(5., 5., 0.)),
+        ]);
+        let split = split(&csg, ANCHOR_Y).unwrap();
+        assert_eq!(count(&split.anchored), 12);
+        assert_eq!(split.floating.len(), 2);
+        for island in split.floating.iter() {
+            assert_eq!(count(island), 12);
+            assert!(island
+                .iter_triangles()
+                .flat_map(|triangle| IntoIterator::into_iter(triangle.positions))
+                .all(|Vector(_x, y, _z)| y > 4.));
+        }
+    }
+
+    #[test]
+    fn leaves_tiny_pieces_in_the_terrain() {
+        let csg = solid(vec![
+            cube(2., Vector(0., 0., 0.)),
+            cube(MIN_ISLAND_EXTENT / 2., Vector(0., 5., 0.)),
+        ]);
+        assert!(split(&csg, ANCHOR_Y).is_none());
+
+        let csg = solid(vec![
+            cube(2., Vector(0., 0., 0.)),
+            cube(MIN_ISLAND_EXTENT / 2., Vector(0., 5., 0.)),
+            cube(1., Vector(5., 5., 0.)),
+        ]);
+        let split = split(&csg, ANCHOR_Y).unwrap();
+        assert_eq!(count(&split.anchored), 24);
+        assert_eq!(split.floating.len(), 1);
+    }
+
+    #[test]
+    fn everything_floating() {
+        let csg = solid(vec![cube(1., Vector(0., 5., 0.))]);
+        let split = split(&csg, ANCHOR_Y).unwrap();
+        assert_eq!(count(&split.anchored), 0);
+        assert_eq!(split.floating.len(), 1);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::entity::EntityKind;
+use rapier3d::geometry::InteractionGroups;
+
+/// A named collision layer, each one a bit of the collision groups given to colliders.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Terrain,
+    Player,
+    /// Balls, props, debris and anything else untagged.
+    Props,
+    Trigger,
+    /// Nothing collides with these by default, queries included.
+    Tools,
+}
+
+impl Layer {
+    const COUNT: usize = 5;
+
+    pub fn bit(self) -> u16 {
+        1 << self as u16
+    }
+
+    /// The layer colliders of `kind` go on.
+    pub fn of(kind: Option<EntityKind>) -> Self {
+        match kind {
+            Some(EntityKind::Terrain) => Layer::Terrain,
+            Some(EntityKind::Player) => Layer::Player,
+            Some(EntityKind::Trigger) => Layer::Trigger,
+            Some(EntityKind::Tool) => Layer::Tools,
+            Some(EntityKind::Ball) | Some(EntityKind::Debris) | Some(EntityKind::Prop) | None => {
+                Layer::Props
+            }
+        }
+    }
+}
+
+/// A set of layers, used to pick what a query can hit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LayerMask(u16);
+
+impl LayerMask {
+    pub const ALL: LayerMask = LayerMask(u16::MAX);
+
+    pub fn without(self, layer: Layer) -> Self {
+        LayerMask(self.0 & !layer.bit())
+    }
+
+    /// The groups to query with so that only colliders on these layers are hit.
+    pub fn query_groups(self) -> InteractionGroups {
+        InteractionGroups::new(u16::MAX, self.0)
+    }
+}
+
+impl From<Layer> for LayerMask {
+    fn from(layer: Layer) -> Self {
+        LayerMask(layer.bit())
+    }
+}
+
+impl std::ops::BitOr for LayerMask {
+    type Output = LayerMask;
+
+    fn bitor(self, rhs: LayerMask) -> LayerMask {
+        LayerMask(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOr<Layer> for LayerMask {
+    type Output = LayerMask;
+
+    fn bitor(self, rhs: Layer) -> LayerMask {
+        self | LayerMask::from(rhs)
+    }
+}
+
+/// Which layers collide with which. Always symmetric, everything but tools collides by default.
+#[derive(Clone, Debug)]
+pub struct CollisionMatrix {
+    filters: [u16; Layer::COUNT],
+}
+
+impl Default for CollisionMatrix {
+    fn default() -> Self {
+        // an empty filter also keeps queries, which are on every layer, from hitting tools
+        let mut filters = [!Layer::Tools.bit(); Layer::COUNT];
+        filters[Layer::Tools as usize] = 0;
+        Self { filters }
+    }
+}
+
+impl CollisionMatrix {
+    pub fn set(&mut self, a: Layer, b: Layer, collide: bool) {
+        if collide {
+            self.filters[a as usize] |= b.bit();
+            self.filters[b as usize] |= a.bit();
+        } else {
+            self.filters[a as usize] &= !b.bit();
+            self.filters[b as usize] &= !a.bit();
+        }
+    }
+
+    /// The collision groups to give a collider on `layer`.
+    pub fn groups(&self, layer: Layer) -> InteractionGroups {
+        InteractionGroups::new(layer.bit(), self.filters[layer as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYERS: [Layer; Layer::COUNT] = [
+        Layer::Terrain,
+        Layer::Player,
+        Layer::Props,
+        Layer::Trigger,
+        Layer::Tools,
+    ];
+
+    fn collide(matrix: &CollisionMatrix, a: Layer, b: Layer) -> bool {
+        matrix.groups(a).test(matrix.groups(b))
+    }
+
+    #[test]
+    fn matrix_stays_symmetric() {
+        let mut matrix = CollisionMatrix::default();
+        matrix.set(Layer::Player, Layer::Props, false);
+        for a in LAYERS.iter() {
+            for b in LAYERS.iter() {
+                let expected = !matches!(
+                    (a, b),
+                    (Layer::Player, Layer::Props)
+                        | (Layer::Props, Layer::Player)
+                        | (Layer::Tools, _)
+                        | (_, Layer::Tools)
+                );
+                assert_eq!(collide(&matrix, *a, *b), expected, "{:?} {:?}", a, b);
+                assert_eq!(collide(&matrix, *a, *b), collide(&matrix, *b, *a));
+            }
+        }
+
+        matrix.set(Layer::Props, Layer::Player, true);
+        assert!(collide(&matrix, Layer::Player, Layer::Props));
+        assert!(collide(&matrix, Layer::Props, Layer::Player));
+    }
+
+    #[test]
+    fn masks_only_hit_their_layers() {
+        let matrix = CollisionMatrix::default();
+        let mask = LayerMask::from(Layer::Terrain) | Layer::Props;
+        for layer in LAYERS.iter() {
+            let expected = matches!(layer, Layer::Terrain | Layer::Props);
+            assert_eq!(mask.query_groups().test(matrix.groups(*layer)), expected);
+        }
+
+        let mask = LayerMask::ALL.without(Layer::Trigger);
+        for layer in LAYERS.iter() {
+            let expected = !matches!(layer, Layer::Trigger | Layer::Tools);
+            assert_eq!(mask.query_groups().test(matrix.groups(*layer)), expected);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::entity::EntityKind;
+use crate::platforms::{Easing, PathMode};
+use crate::triggers::TriggerKind;
+
+/// Where the player starts, and respawns until a checkpoint is reached.
+pub const PLAYER_SPAWN: [f32; 3] = [0., 4., 0.];
+
+pub struct TriggerPlacement {
+    pub kind: TriggerKind,
+    pub center: [f32; 3],
+    pub half_extents: [f32; 3],
+    pub watches: &'static [EntityKind],
+}
+
+pub const TRIGGERS: &[TriggerPlacement] = &[
+    TriggerPlacement {
+        kind: TriggerKind::Checkpoint,
+        center: [8., -1., 8.],
+        half_extents: [1.5, 1., 1.5],
+        watches: &[EntityKind::Player],
+    },
+    TriggerPlacement {
+        kind: TriggerKind::Goal,
+        center: [-10., -1., -10.],
+        half_extents: [1.5, 1., 1.5],
+        watches: &[EntityKind::Player],
+    },
+    TriggerPlacement {
+        kind: TriggerKind::Hazard,
+        center: [10., -1.5, -10.],
+        half_extents: [2., 0.5, 2.],
+        watches: &[EntityKind::Player, EntityKind::Ball],
+    },
+];
+
+pub struct FluidPlacement {
+    pub center: [f32; 3],
+    pub half_extents: [f32; 3],
+    /// Height of the surface, which can be below the top of the box.
+    pub surface_y: f32,
+}
+
+pub const FLUIDS: &[FluidPlacement] = &[
+    // a shallow pool on the floor of the pit
+    FluidPlacement {
+        center: [0., -1.5, 11.],
+        half_extents: [3., 0.5, 3.],
+        surface_y: -1.2,
+    },
+];
+
+pub struct PlatformPlacement {
+    pub half_extents: [f32; 3],
+    pub waypoints: &'static [[f32; 3]],
+    /// Seconds spent going from one waypoint to the next.
+    pub segment_duration: f32,
+    pub easing: Easing,
+    pub mode: PathMode,
+}
+
+pub const PLATFORMS: &[PlatformPlacement] = &[
+    // a lift out of the pit
+    PlatformPlacement {
+        half_extents: [1.5, 0.2, 1.5],
+        waypoints: &[[-8., -1.7, -4.], [-8., 5., -4.]],
+        segment_duration: 4.,
+        easing: Easing::SmoothStep,
+        mode: PathMode::PingPong,
+    },
+    // does laps above the pit, reachable from the top of the lift
+    PlatformPlacement {
+        half_extents: [1.5, 0.2, 1.5],
+        waypoints: &[[-5., 6., -5.], [7., 6., -5.], [7., 6., 5.], [-1., 6., 5.]],
+        segment_duration: 3.,
+        easing: Easing::Linear,
+        mode: PathMode::Loop,
+    },
+];
@@ -1,21 +1,71 @@
+mod character;
+mod debug_draw;
+mod entity;
+mod explosions;
+mod fluids;
+mod grab;
+mod gravity;
+mod islands;
+mod layers;
+mod level;
+mod lifetimes;
+mod platforms;
+mod snapshot;
+mod spawner;
+mod throwing;
+mod triggers;
 mod trimesh;
 #[cfg(target_arch = "wasm32")]
 pub mod web;
+mod world;
 
 #[cfg(not(target_arch = "wasm32"))]
 use glutin::event::{ElementState, MouseButton, VirtualKeyCode};
 #[cfg(target_arch = "wasm32")]
 use winit::event::{ElementState, MouseButton, VirtualKeyCode};
 
+use entity::EntityKind;
+use explosions::Explosion;
+use fluids::{FluidBounds, FluidVolume};
+use gravity::{GravityField, GravityMode, GravityZone};
+use layers::{CollisionMatrix, Layer};
+use physics::QueryFilter;
 use rapier3d::dynamics::RigidBodyBuilder;
-use rapier3d::geometry::{ColliderBuilder, ColliderHandle, Ray};
+use rapier3d::geometry::{Collider, ColliderBuilder, ColliderHandle, Ray, Shape, AABB};
+use rapier3d::math::Isometry;
 use rapier3d::na::{Point2, Point3, UnitQuaternion, Vector2, Vector3};
 use solstice_2d::{
     solstice::{self, Context},
     Color, Draw, Transform3D,
 };
+use spawner::{SpawnerConfig, SpawnerId};
+use std::collections::HashMap;
+use triggers::{Trigger, TriggerEvent, TriggerKind};
 
 const MAX_VERTS: usize = 1_000_000;
+const BRUSH_RADIUS: f32 = 3.;
+// anything still touching the bottom of the world doesn't fall
+const GROUND_ANCHOR_Y: f32 = -30. + 0.01;
+const PHYSICS_HZ: f32 = 60.;
+const MAX_PHYSICS_SUBSTEPS: u32 = 5;
+// low enough for interpolation to be the only thing keeping motion smooth
+const SLOW_PHYSICS_HZ: f32 = 10.;
+// impacts on the player harder than this count as a hit
+const PLAYER_HIT_IMPULSE: f32 = 5.;
+// keeps a zero interval from spawning every frame
+const MIN_SPAWN_INTERVAL: f32 = 0.05;
+const EXPLOSION_RADIUS: f32 = 2.5;
+const HAZARD_EXPLOSION_RADIUS: f32 = 2.;
+const BOMB_FUSE: std::time::Duration = std::time::Duration::from_secs(3);
+// chunks of terrain thrown out of every crater
+const RUBBLE_PIECES: usize = 6;
+const RUBBLE_SIZE: f32 = 0.3;
+// how hard a digger has to hit the terrain to carve it, and how much it takes out
+const DIG_IMPULSE: f32 = 20.;
+const DIG_RADIUS: f32 = 0.6;
+// gap between the capsule's axis and a thrown ball, enough to clear the capsule
+const THROW_CLEARANCE: f32 = 0.6;
+const ARC_COLOR: [f32; 4] = [1., 0.8, 0.2, 1.];
 
 pub enum MouseEvent {
     Button(ElementState, MouseButton),
@@ -28,10 +78,25 @@ struct InputState {
     a: bool,
     s: bool,
     d: bool,
+    r: bool,
+    f: bool,
     prev_mouse_position: (f32, f32),
     mouse_position: (f32, f32),
 }
 
+struct Debris {
+    collider: ColliderHandle,
+    geometry: solstice::mesh::VertexMesh<solstice_2d::Vertex3D>,
+    vert_count: usize,
+    // kept around for snapshots
+    csg: rscsg::dim3::Csg,
+}
+
+struct PropMesh {
+    geometry: solstice::mesh::VertexMesh<solstice_2d::Vertex3D>,
+    vert_count: usize,
+}
+
 pub struct Resources {
     pub debug_font_data: Vec<u8>,
 }
@@ -41,16 +106,32 @@ pub struct Game {
     brush: rscsg::dim3::Csg,
     geometry: solstice::mesh::VertexMesh<solstice_2d::Vertex3D>,
     vert_count: usize,
+    debris: Vec<Debris>,
+    prop_meshes: HashMap<ColliderHandle, PropMesh>,
+    fluid_surfaces: Vec<solstice::mesh::VertexMesh<solstice_2d::Vertex3D>>,
     capsule: solstice::mesh::IndexedMesh<solstice_2d::Vertex3D, u32>,
-    physics: physics::PhysicsContext,
+    world: world::World,
     ctx: Context,
     gfx: solstice_2d::Graphics,
     debug_font_id: solstice_2d::FontId,
     shader: solstice_2d::Shader,
     camera: camera::CameraState,
     input_state: InputState,
+    character: character::CharacterController,
+    thrower: throwing::Thrower,
+    grab: grab::GrabTool,
+    hits: u32,
+    goals: u32,
+    // where the player goes back to after touching a hazard
+    checkpoint: Point3<f32>,
+    debug_draw: bool,
+    last_ray: Option<(Point3<f32>, Point3<f32>)>,
     time: std::time::Duration,
-    cron: cron::Cron<physics::PhysicsContext>,
+    timestep: timestep::FixedTimestep,
+    cron: cron::Cron<world::World>,
+    spawner_jobs: HashMap<SpawnerId, cron::JobId>,
+    default_spawner: SpawnerId,
+    quicksave: Option<snapshot::Snapshot>,
 
     ground_handle: ColliderHandle,
     capsule_handle: ColliderHandle,
@@ -64,9 +145,15 @@ impl Game {
         height: f32,
         resources: Resources,
     ) -> eyre::Result<Self> {
+        let timestep = timestep::FixedTimestep::new(PHYSICS_HZ, MAX_PHYSICS_SUBSTEPS);
         let mut physics = physics::PhysicsContext::new(0., -9.81, 0.);
+        let mut collision_matrix = CollisionMatrix::default();
+        // triggers only care about things moving through them
+        collision_matrix.set(Layer::Trigger, Layer::Terrain, false);
+        collision_matrix.set(Layer::Trigger, Layer::Trigger, false);
+        physics.set_collision_matrix(collision_matrix);
 
-        let brush = rscsg::dim3::Csg::sphere(rscsg::dim3::Vector(0., 0., 0.), 3., 10, 10);
+        let brush = rscsg::dim3::Csg::sphere(rscsg::dim3::Vector(0., 0., 0.), BRUSH_RADIUS, 10, 10);
 
         let csg = rscsg::dim3::Csg::subtract(
             &rscsg::dim3::Csg::cube(rscsg::dim3::Vector(30., 30., 30.), true)
@@ -79,7 +166,11 @@ impl Game {
         geometry.set_vertices(&mut ctx, &vertices, 0);
 
         let (capsule_handle, capsule) = {
-            let coll = ColliderBuilder::capsule_y(1., 0.5).build();
+            // the character controller handles slowing down so friction only gets in the way
+            let coll = ColliderBuilder::capsule_y(1., 0.5)
+                .friction(0.)
+                .user_data(EntityKind::Player.user_data())
+                .build();
             let (vertices, indices) = coll.shape().as_capsule().unwrap().to_trimesh(20, 20);
 
             let vertices = vertices
@@ -103,7 +194,11 @@ impl Game {
             let capsule = solstice::mesh::IndexedMesh::with_data(&mut ctx, &vertices, &indices)?;
 
             let rb = RigidBodyBuilder::new_dynamic()
-                .translation(0., 4., 0.)
+                .translation(
+                    level::PLAYER_SPAWN[0],
+                    level::PLAYER_SPAWN[1],
+                    level::PLAYER_SPAWN[2],
+                )
                 .restrict_rotations(false, false, false)
                 .build();
             (physics.add_body(rb, coll), capsule)
@@ -114,69 +209,232 @@ impl Game {
             gfx.add_font(std::convert::TryInto::try_into(resources.debug_font_data)?);
         let shader = solstice_2d::Shader::with(include_str!("shader.glsl"), &mut ctx)?;
 
-        let mut cron = cron::Cron::default();
-        cron.every(
-            std::time::Duration::from_secs_f32(1.5),
-            |physics: &mut physics::PhysicsContext| {
-                let collider = ColliderBuilder::ball(1.).user_data(1).build();
-                let body = RigidBodyBuilder::new_dynamic()
-                    .translation(0., 10., 0.)
-                    .user_data(1)
-                    .build();
-
-                physics.add_body(body, collider);
+        for placement in level::TRIGGERS {
+            let trigger = Trigger {
+                kind: placement.kind,
+                watches: placement.watches.to_vec(),
+            };
+            physics.add_trigger(
+                trigger,
+                &placement.center.into(),
+                &placement.half_extents.into(),
+            );
+        }
+
+        let mut world = world::World::new(physics, Default::default(), Default::default());
+        world.add_wrecking_ball(Point3::new(-6., 8., 0.), 6);
+        world.add_door(Point3::new(0., 0., -8.), Vector3::new(1.5, 1.9, 0.1));
+        world.add_planetoid(Point3::new(0., 18., -20.), 3., 6.);
+        // pulls anything near the +x wall onto it
+        world.physics.add_gravity_zone(GravityZone {
+            field: GravityField::Directional {
+                bounds: AABB::new(Point3::new(10., -2., -14.), Point3::new(14., 10., 14.)),
+                acceleration: Vector3::new(9.81, 0., 0.),
             },
-        );
+            mode: GravityMode::Override,
+        });
+        for placement in level::PLATFORMS {
+            let path = platforms::Path {
+                waypoints: placement
+                    .waypoints
+                    .iter()
+                    .map(|waypoint| Point3::from(*waypoint))
+                    .collect(),
+                segment_duration: placement.segment_duration,
+                easing: placement.easing,
+                mode: placement.mode,
+            };
+            world.add_platform(path, placement.half_extents.into());
+        }
 
-        Ok(Self {
+        let mut game = Self {
             csg,
             brush,
             geometry,
             vert_count: vertices.len(),
+            debris: vec![],
+            prop_meshes: HashMap::new(),
+            fluid_surfaces: vec![],
             capsule,
-            physics,
+            world,
             ctx,
             gfx,
             debug_font_id,
             shader,
             camera: camera::CameraState::new(),
             input_state: InputState::default(),
+            character: character::CharacterController::new(Default::default()),
+            thrower: throwing::Thrower::new(Default::default()),
+            grab: grab::GrabTool::new(Default::default()),
+            hits: 0,
+            goals: 0,
+            checkpoint: level::PLAYER_SPAWN.into(),
+            debug_draw: false,
+            last_ray: None,
             time,
-            cron,
+            timestep,
+            cron: cron::Cron::default(),
+            spawner_jobs: HashMap::new(),
+            default_spawner: SpawnerId::default(),
+            quicksave: None,
             ground_handle,
             capsule_handle,
-        })
+        };
+        game.default_spawner = game.add_spawner(SpawnerConfig {
+            digger: true,
+            ..Default::default()
+        });
+        let basin = rscsg::dim3::Csg::cube(rscsg::dim3::Vector(10., 4., 10.), true)
+            .translate(rscsg::dim3::Vector(-9., -2., 9.));
+        game.add_fluid(FluidVolume::new(FluidBounds::from_csg(&basin), -0.5))?;
+        for placement in level::FLUIDS {
+            let center = Point3::from(placement.center);
+            let half_extents = Vector3::from(placement.half_extents);
+            let bounds = FluidBounds::Aabb(AABB::new(center - half_extents, center + half_extents));
+            game.add_fluid(FluidVolume::new(bounds, placement.surface_y))?;
+        }
+        Ok(game)
+    }
+
+    /// Captures the simulation and the terrain it's running on.
+    pub fn snapshot(&self) -> snapshot::Snapshot {
+        snapshot::Snapshot {
+            physics: self.world.physics.snapshot(),
+            terrain: snapshot::Solid::new(&self.csg),
+            ground: self.ground_handle,
+            capsule: self.capsule_handle,
+            debris: self
+                .debris
+                .iter()
+                .map(|debris| (debris.collider, snapshot::Solid::new(&debris.csg)))
+                .collect(),
+            spawned: self.world.spawned.ages(),
+            debris_ages: self.world.debris.ages(),
+            platforms: self.world.platforms.times(),
+        }
+    }
+
+    /// Puts the simulation and terrain back the way they were when `snapshot` was taken.
+    pub fn restore(&mut self, snapshot: &snapshot::Snapshot) {
+        self.grab.release(&mut self.world.physics);
+        self.csg = snapshot.terrain.to_csg();
+        self.world.physics.restore(&snapshot.physics);
+        self.ground_handle = snapshot.ground;
+        self.capsule_handle = snapshot.capsule;
+        self.world.spawned.restore(snapshot.spawned.iter().copied());
+        self.world
+            .debris
+            .restore(snapshot.debris_ages.iter().copied());
+        self.world
+            .platforms
+            .restore(snapshot.platforms.iter().copied());
+
+        self.debris.clear();
+        for (handle, solid) in snapshot.debris.iter() {
+            self.add_debris(*handle, solid.to_csg());
+        }
+        self.prop_meshes.clear();
+        self.upload_terrain();
+    }
+
+    pub fn add_fluid(&mut self, fluid: FluidVolume) -> eyre::Result<()> {
+        let color = [0.2, 0.4, 1., 0.5];
+        let vertices = fluid
+            .surface()
+            .iter()
+            .map(|corner| solstice_2d::Vertex3D {
+                position: [corner.x, corner.y, corner.z],
+                uv: [0., 0.],
+                color,
+                normal: [0., 1., 0.],
+            })
+            .collect::<Vec<_>>();
+        let triangles = [0, 1, 2, 0, 2, 3]
+            .iter()
+            .map(|i| vertices[*i])
+            .collect::<Vec<_>>();
+        let surface = solstice::mesh::VertexMesh::with_data(&mut self.ctx, &triangles)?;
+        self.fluid_surfaces.push(surface);
+        self.world.physics.add_fluid(fluid);
+        Ok(())
+    }
+
+    /// Starts spawning bodies as described by `config`.
+    pub fn add_spawner(&mut self, config: SpawnerConfig) -> SpawnerId {
+        let interval = spawn_interval(&config);
+        let id = self.world.spawners.insert(config);
+        let job = self.cron.every(interval, move |world: &mut world::World| {
+            world.run_spawner(id)
+        });
+        self.spawner_jobs.insert(id, job);
+        id
+    }
+
+    /// Edits the spawner `id` in place. Changes apply from its next burst.
+    pub fn update_spawner<F>(&mut self, id: SpawnerId, f: F)
+    where
+        F: FnOnce(&mut SpawnerConfig),
+    {
+        if let Some(config) = self.world.spawners.get_mut(id) {
+            f(config);
+            if let Some(job) = self.spawner_jobs.get(&id) {
+                self.cron.set_interval(*job, spawn_interval(config));
+            }
+        }
+    }
+
+    /// Stops the spawner `id`. Whatever it already spawned is left alone.
+    pub fn remove_spawner(&mut self, id: SpawnerId) -> Option<SpawnerConfig> {
+        if let Some(job) = self.spawner_jobs.remove(&id) {
+            self.cron.remove(job);
+        }
+        self.world.spawners.remove(id)
     }
 
     pub fn update(&mut self, time: std::time::Duration) {
         let dt = time - self.time;
         for callback in self.cron.update(dt) {
-            (callback)(&mut self.physics)
+            (callback)(&mut self.world)
         }
         self.time = time;
 
-        if let Some(capsule) = self.physics.rigid_body_mut(self.capsule_handle) {
-            let mut v = Vector3::zeros();
-            let speed = 1.;
-            if self.input_state.w {
-                v += Vector3::new(0., 0., -speed);
-            }
-            if self.input_state.a {
-                v += Vector3::new(-speed, 0., 0.);
-            }
-            if self.input_state.s {
-                v += Vector3::new(0., 0., speed);
-            }
-            if self.input_state.d {
-                v += Vector3::new(speed, 0., 0.);
-            }
-            let v = self.camera.pivot.transform_vector(&v);
-            capsule.apply_impulse(v, true);
+        for _ in 0..self.timestep.advance(dt) {
+            self.fixed_update();
         }
-
-        self.physics.step();
-        self.camera
-            .update(self.physics.collider_position(self.capsule_handle));
+        let trigger_events = self
+            .world
+            .physics
+            .drain_trigger_events()
+            .collect::<Vec<_>>();
+        for event in trigger_events {
+            self.handle_trigger_event(event);
+        }
+        self.sync_prop_meshes();
+        let alpha = self.timestep.alpha();
+        let capsule_position = self
+            .world
+            .physics
+            .interpolated_position(self.capsule_handle, alpha);
+        self.camera.update(capsule_position.as_ref());
+        let throw_arc = self
+            .thrower
+            .speed()
+            .and_then(|speed| self.throw_start(speed))
+            .map(|(origin, velocity)| {
+                throwing::predict_arc(
+                    &self.world.physics,
+                    &origin,
+                    &velocity,
+                    self.thrower.config.radius,
+                )
+                .into_iter()
+                .map(|point| solstice_2d::LineVertex {
+                    position: [point.x, point.y, point.z],
+                    width: 2.,
+                    color: ARC_COLOR,
+                })
+                .collect::<Vec<_>>()
+            });
 
         let mut g = self.gfx.lock(&mut self.ctx);
         g.clear(Color::new(0., 0., 0., 1.));
@@ -195,9 +453,31 @@ impl Game {
         };
         g.draw(geometry);
 
-        for collider in self.physics.colliders() {
+        for debris in self.debris.iter() {
+            if let Some(position) = self
+                .world
+                .physics
+                .interpolated_position(debris.collider, alpha)
+            {
+                g.draw_with_transform(
+                    solstice::Geometry {
+                        mesh: &debris.geometry,
+                        draw_range: 0..debris.vert_count,
+                        draw_mode: solstice::DrawMode::Triangles,
+                        instance_count: 1,
+                    },
+                    iso_into_tx(&position),
+                );
+            }
+        }
+
+        for (handle, collider) in self.world.physics.entities(EntityKind::Ball) {
             if let Some(ball) = collider.shape().as_ball() {
-                let position = collider.position();
+                let position = self
+                    .world
+                    .physics
+                    .interpolated_position(handle, alpha)
+                    .unwrap_or(*collider.position());
                 g.draw_with_transform(
                     solstice_2d::Sphere {
                         radius: ball.radius,
@@ -205,12 +485,26 @@ impl Game {
                         height_segments: 10,
                         ..Default::default()
                     },
-                    iso_into_tx(position),
+                    iso_into_tx(&position),
+                );
+            }
+        }
+
+        for (handle, prop) in self.prop_meshes.iter() {
+            if let Some(position) = self.world.physics.interpolated_position(*handle, alpha) {
+                g.draw_with_transform(
+                    solstice::Geometry {
+                        mesh: &prop.geometry,
+                        draw_range: 0..prop.vert_count,
+                        draw_mode: solstice::DrawMode::Triangles,
+                        instance_count: 1,
+                    },
+                    iso_into_tx(&position),
                 );
             }
         }
 
-        if let Some(position) = self.physics.collider_position(self.capsule_handle) {
+        if let Some(position) = capsule_position.as_ref() {
             g.draw_with_transform(
                 solstice::Geometry {
                     mesh: &self.capsule,
@@ -220,7 +514,34 @@ impl Game {
                 },
                 iso_into_tx(position),
             );
+        }
+
+        // translucent so it goes after everything opaque
+        g.set_shader(None);
+        for surface in self.fluid_surfaces.iter() {
+            g.draw(solstice::Geometry {
+                mesh: surface,
+                draw_range: 0..6,
+                draw_mode: solstice::DrawMode::Triangles,
+                instance_count: 1,
+            });
+        }
+
+        if let Some(arc) = throw_arc {
+            g.set_shader(None);
+            g.line_3d(arc);
+        }
 
+        // on top of the whole scene: `line_2d` keeps the perspective camera but skips the depth
+        // test, so colliders buried in the terrain still show up
+        if self.debug_draw {
+            g.set_shader(None);
+            for line in debug_draw::lines(&self.world.physics, self.last_ray.as_ref()) {
+                g.line_2d(line);
+            }
+        }
+
+        if let Some(position) = capsule_position.as_ref() {
             g.set_shader(None);
             g.set_projection_mode(None);
             g.set_camera(Transform3D::default());
@@ -252,10 +573,150 @@ impl Game {
                 self.debug_font_id,
                 16.,
                 solstice_2d::Rectangle::new(0., font_scale * 3., 720., 720.),
+            );
+            g.print(
+                format!("grounded: {}", self.character.is_grounded()),
+                self.debug_font_id,
+                16.,
+                solstice_2d::Rectangle::new(0., font_scale * 4., 720., 720.),
+            );
+            g.print(
+                format!("hits: {}", self.hits),
+                self.debug_font_id,
+                16.,
+                solstice_2d::Rectangle::new(0., font_scale * 5., 720., 720.),
+            );
+            g.print(
+                format!(
+                    "spawned: {}/{}",
+                    self.world.spawned.len(),
+                    self.world.spawned.rules.max_live
+                ),
+                self.debug_font_id,
+                16.,
+                solstice_2d::Rectangle::new(0., font_scale * 6., 720., 720.),
+            );
+            g.print(
+                format!("goals: {}", self.goals),
+                self.debug_font_id,
+                16.,
+                solstice_2d::Rectangle::new(0., font_scale * 7., 720., 720.),
             )
         }
     }
 
+    // advances the simulation by exactly one physics tick
+    fn fixed_update(&mut self) {
+        self.set_off_explosions();
+        self.thrower.update(self.timestep.dt());
+
+        let mut v = Vector3::zeros();
+        if self.input_state.w {
+            v += Vector3::new(0., 0., -1.);
+        }
+        if self.input_state.a {
+            v += Vector3::new(-1., 0., 0.);
+        }
+        if self.input_state.s {
+            v += Vector3::new(0., 0., 1.);
+        }
+        if self.input_state.d {
+            v += Vector3::new(1., 0., 0.);
+        }
+        let v = self.camera.pivot.transform_vector(&v);
+        self.character.update(
+            &mut self.world.physics,
+            self.capsule_handle,
+            v,
+            self.timestep.dt(),
+        );
+
+        let ray = self.cursor_ray();
+        let depth = self.input_state.r as i32 - self.input_state.f as i32;
+        self.grab.set_depth_input(depth as f32);
+        self.grab
+            .update(&mut self.world.physics, &ray, self.timestep.dt());
+
+        self.world.step(self.timestep.dt());
+
+        let events = self.world.physics.drain_events().collect::<Vec<_>>();
+        let mut digs = vec![];
+        for event in events {
+            if let Some(point) = self.dig_point(&event) {
+                digs.push((point, DIG_RADIUS));
+            }
+            self.handle_collision_event(event);
+        }
+        self.carve_terrain(&digs);
+    }
+
+    // where a digger hit the terrain hard enough to carve it, if that's what happened
+    fn dig_point(&self, event: &physics::CollisionEvent) -> Option<Point3<f32>> {
+        if event.kind != physics::CollisionEventKind::ContactStarted || event.impulse < DIG_IMPULSE
+        {
+            return None;
+        }
+        let (other, _) = event.other(self.ground_handle)?;
+        if self.world.physics.is_digger(other) {
+            event.point
+        } else {
+            None
+        }
+    }
+
+    fn handle_collision_event(&mut self, event: physics::CollisionEvent) {
+        use physics::CollisionEventKind;
+
+        let other = match event.other(self.capsule_handle) {
+            Some((_, other)) => other,
+            None => return,
+        };
+        // only the spawned balls count
+        if event.kind == CollisionEventKind::ContactStarted
+            && other == Some(EntityKind::Ball)
+            && event.impulse >= PLAYER_HIT_IMPULSE
+        {
+            self.hits += 1;
+        }
+    }
+
+    fn handle_trigger_event(&mut self, event: TriggerEvent) {
+        if !event.entered {
+            return;
+        }
+        match (event.kind, event.other_kind) {
+            (TriggerKind::Checkpoint, EntityKind::Player) => {
+                if let Some(trigger) = self.world.physics.collider(event.trigger) {
+                    // respawn above the checkpoint rather than inside the floor
+                    self.checkpoint =
+                        Point3::from(trigger.position().translation.vector) + Vector3::y() * 2.;
+                }
+            }
+            (TriggerKind::Goal, EntityKind::Player) => self.goals += 1,
+            (TriggerKind::Hazard, EntityKind::Player) => {
+                let checkpoint = self.checkpoint;
+                if let Some(body) = self.world.physics.rigid_body_mut(self.capsule_handle) {
+                    body.set_position(
+                        Isometry::translation(checkpoint.x, checkpoint.y, checkpoint.z),
+                        true,
+                    );
+                    body.set_linvel(Vector3::zeros(), true);
+                }
+            }
+            (TriggerKind::Hazard, other_kind) => {
+                if let Some(body) = self.world.physics.remove_body(event.other) {
+                    // balls go off where they fall in
+                    if other_kind == EntityKind::Ball {
+                        let center = Point3::from(body.position().translation.vector);
+                        self.world
+                            .explode(Explosion::new(center, HAZARD_EXPLOSION_RADIUS));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
     pub fn handle_key_event(&mut self, state: ElementState, key_code: VirtualKeyCode) {
         let pressed = match state {
             ElementState::Pressed => true,
@@ -266,75 +727,158 @@ impl Game {
             VirtualKeyCode::A => self.input_state.a = pressed,
             VirtualKeyCode::S => self.input_state.s = pressed,
             VirtualKeyCode::D => self.input_state.d = pressed,
-            VirtualKeyCode::Space => {
-                if let Some(body) = self.physics.rigid_body_mut(self.capsule_handle) {
-                    body.apply_impulse(Vector3::new(0., 5., 0.), true)
+            VirtualKeyCode::R => self.input_state.r = pressed,
+            VirtualKeyCode::F => self.input_state.f = pressed,
+            VirtualKeyCode::Space if pressed => self.character.jump(),
+            VirtualKeyCode::F1 if pressed => self.debug_draw = !self.debug_draw,
+            VirtualKeyCode::F3 if pressed => {
+                // toggles between the normal and slow rates
+                let hz = if self.timestep.dt() > 2. / PHYSICS_HZ {
+                    PHYSICS_HZ
+                } else {
+                    SLOW_PHYSICS_HZ
+                };
+                self.timestep.set_hz(hz);
+            }
+            VirtualKeyCode::F5 if pressed => self.quicksave = Some(self.snapshot()),
+            VirtualKeyCode::F9 if pressed => {
+                if let Some(snapshot) = self.quicksave.take() {
+                    self.restore(&snapshot);
+                    self.quicksave = Some(snapshot);
+                }
+            }
+            VirtualKeyCode::Q => {
+                if pressed {
+                    self.thrower.start_charging();
+                } else if let Some(speed) = self.thrower.release() {
+                    self.throw(speed);
+                }
+            }
+            VirtualKeyCode::E if pressed => {
+                if let Some(point) = self.terrain_under_cursor() {
+                    self.world.explode(Explosion::new(point, EXPLOSION_RADIUS));
                 }
             }
+            VirtualKeyCode::B if pressed => {
+                if let Some(point) = self.terrain_under_cursor() {
+                    self.cron.after(BOMB_FUSE, move |world: &mut world::World| {
+                        world.explode(Explosion::new(point, EXPLOSION_RADIUS))
+                    });
+                }
+            }
+            VirtualKeyCode::F2 if pressed => {
+                self.update_spawner(self.default_spawner, |config| {
+                    config.shape = config.shape.next()
+                });
+            }
             _ => {}
         };
     }
 
+    // where a throw at `speed` leaves from and its velocity, if there's a player to throw it
+    fn throw_start(&self, speed: f32) -> Option<(Point3<f32>, Vector3<f32>)> {
+        let capsule = self.world.physics.collider(self.capsule_handle)?;
+        let forward = self.camera.position.rotation * -Vector3::z();
+        let velocity = self.thrower.launch_velocity(&forward, speed);
+        let horizontal = Vector3::new(velocity.x, 0., velocity.z)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::zeros);
+        let origin = Point3::from(capsule.position().translation.vector)
+            + horizontal * (THROW_CLEARANCE + self.thrower.config.radius)
+            + Vector3::y() * 0.5;
+        Some((origin, velocity))
+    }
+
+    fn throw(&mut self, speed: f32) {
+        if let Some((origin, velocity)) = self.throw_start(speed) {
+            let body = RigidBodyBuilder::new_dynamic()
+                .translation(origin.x, origin.y, origin.z)
+                .linvel(velocity.x, velocity.y, velocity.z)
+                .build();
+            let collider = ColliderBuilder::ball(self.thrower.config.radius)
+                .user_data(EntityKind::Ball.user_data())
+                .build();
+            self.world.spawn(body, collider);
+        }
+    }
+
+    // from the camera through the mouse cursor
+    fn cursor_ray(&self) -> Ray {
+        let (x, y) = self.input_state.mouse_position;
+        let (w, h) = (1280., 720.);
+        let (point, direction) = self
+            .camera
+            .unproject(&Point2::new(x, y), &Vector2::new(w, h));
+        Ray::new(point, direction)
+    }
+
+    // the point on the terrain under the mouse cursor
+    fn terrain_under_cursor(&self) -> Option<Point3<f32>> {
+        let ray = self.cursor_ray();
+        let (_collider, distance) = self.world.physics.cast_ray(&ray, Layer::Terrain.into())?;
+        Some(ray.point_at(distance))
+    }
+
     pub fn handle_mouse_event(&mut self, event: MouseEvent) {
         match event {
             MouseEvent::Button(state, button) => match state {
                 ElementState::Pressed => {
-                    let (x, y) = self.input_state.mouse_position;
-                    let (w, h) = (1280., 720.);
-                    let (point, direction) = self
-                        .camera
-                        .unproject(&Point2::new(x, y), &Vector2::new(w, h));
-                    let ray = Ray::new(point, direction);
+                    let ray = self.cursor_ray();
+                    let terrain_hit = self.terrain_under_cursor();
+                    self.last_ray = Some((
+                        ray.origin,
+                        terrain_hit.unwrap_or_else(|| ray.point_at(100.)),
+                    ));
                     match button {
+                        MouseButton::Left if self.grab.is_grabbing() => {
+                            self.grab.throw(&mut self.world.physics, &ray);
+                        }
                         MouseButton::Left => {
-                            if let Some((_collider, distance)) = self.physics.cast_ray(&ray) {
-                                let cp: Point3<f32> = point + direction * distance;
+                            if let Some(cp) = terrain_hit {
+                                // unioning on top of a dynamic body embeds it in the new
+                                // trimesh so refuse the edit instead
+                                let physics = &self.world.physics;
+                                let is_dynamic = |_handle: ColliderHandle, collider: &Collider| {
+                                    physics.is_dynamic(collider)
+                                };
+                                let blocked = physics.intersections_with_sphere(
+                                    &cp,
+                                    BRUSH_RADIUS,
+                                    QueryFilter::default().predicate(&is_dynamic),
+                                );
+                                if !blocked.is_empty() {
+                                    return;
+                                }
+
                                 let brush = self
                                     .brush
                                     .clone()
                                     .translate(rscsg::dim3::Vector(cp.x, cp.y, cp.z));
                                 self.csg = rscsg::dim3::Csg::union(&self.csg, &brush);
-                                self.ground_handle = self
-                                    .physics
-                                    .swap_collider(self.ground_handle, &self.csg)
-                                    .unwrap();
-
-                                let vertices = self
-                                    .csg
-                                    .iter_triangles()
-                                    .flat_map(to_vert)
-                                    .collect::<Vec<_>>();
-                                self.geometry.set_vertices(&mut self.ctx, &vertices, 0);
-                                self.vert_count = vertices.len();
+                                self.rebuild_terrain();
                             }
                         }
                         MouseButton::Right => {
-                            if let Some((_collider, distance)) = self.physics.cast_ray(&ray) {
-                                let cp: Point3<f32> = point + direction * distance;
+                            if let Some(cp) = terrain_hit {
                                 let brush = self
                                     .brush
                                     .clone()
                                     .translate(rscsg::dim3::Vector(cp.x, cp.y, cp.z));
                                 self.csg = rscsg::dim3::Csg::subtract(&self.csg, &brush);
-                                self.ground_handle = self
-                                    .physics
-                                    .swap_collider(self.ground_handle, &self.csg)
-                                    .unwrap();
-
-                                let vertices = self
-                                    .csg
-                                    .iter_triangles()
-                                    .flat_map(to_vert)
-                                    .collect::<Vec<_>>();
-                                self.geometry.set_vertices(&mut self.ctx, &vertices, 0);
-                                self.vert_count = vertices.len();
+                                self.rebuild_terrain();
                             }
                         }
-                        MouseButton::Middle => {}
+                        MouseButton::Middle => {
+                            self.grab.grab(&mut self.world.physics, &ray);
+                        }
                         MouseButton::Other(_) => {}
                     }
                 }
-                ElementState::Released => {}
+                ElementState::Released => {
+                    if button == MouseButton::Middle {
+                        self.grab.release(&mut self.world.physics);
+                    }
+                }
             },
             MouseEvent::Moved(x, y) => {
                 if self.input_state.mouse_position == self.input_state.prev_mouse_position
@@ -356,12 +900,205 @@ impl Game {
         }
     }
 
+    // carves every queued explosion out of the terrain, then throws rubble and everything else
+    // nearby clear of it
+    fn set_off_explosions(&mut self) {
+        let explosions = self.world.drain_explosions().collect::<Vec<_>>();
+        let craters = explosions
+            .iter()
+            .filter(|explosion| {
+                !self
+                    .world
+                    .physics
+                    .intersections_with_sphere(
+                        &explosion.center,
+                        explosion.radius,
+                        Layer::Terrain.into(),
+                    )
+                    .is_empty()
+            })
+            .collect::<Vec<_>>();
+        self.carve_terrain(
+            &craters
+                .iter()
+                .map(|explosion| (explosion.center, explosion.radius))
+                .collect::<Vec<_>>(),
+        );
+        for explosion in craters {
+            self.spawn_rubble(explosion);
+        }
+
+        for explosion in explosions.iter() {
+            self.world.physics.apply_radial_impulse(
+                &explosion.center,
+                explosion.blast_radius(),
+                explosion.impulse,
+            );
+        }
+    }
+
+    // subtracts a ball of the given radius around every point from the terrain, rebuilding it
+    // once at the end
+    fn carve_terrain(&mut self, balls: &[(Point3<f32>, f32)]) {
+        if balls.is_empty() {
+            return;
+        }
+        for (center, radius) in balls {
+            let sphere = rscsg::dim3::Csg::sphere(
+                rscsg::dim3::Vector(center.x, center.y, center.z),
+                *radius,
+                10,
+                10,
+            );
+            self.csg = rscsg::dim3::Csg::subtract(&self.csg, &sphere);
+        }
+        self.rebuild_terrain();
+    }
+
+    // small chunks of terrain in the empty crater, ready to be blown outwards
+    fn spawn_rubble(&mut self, explosion: &Explosion) {
+        for i in 0..RUBBLE_PIECES {
+            let angle = i as f32 / RUBBLE_PIECES as f32 * std::f32::consts::TAU;
+            let offset = Vector3::new(angle.cos(), 1., angle.sin()) * (explosion.radius * 0.5);
+            let p = explosion.center + offset;
+            let chunk = rscsg::dim3::Csg::cube(
+                rscsg::dim3::Vector(RUBBLE_SIZE, RUBBLE_SIZE, RUBBLE_SIZE),
+                true,
+            )
+            .translate(rscsg::dim3::Vector(p.x, p.y, p.z));
+            let collider = self.world.physics.add_convex_csg(
+                RigidBodyBuilder::new_dynamic().build(),
+                &chunk,
+                EntityKind::Debris,
+            );
+            self.world.debris.track(&mut self.world.physics, collider);
+            self.add_debris(collider, chunk);
+        }
+    }
+
+    // regenerates the ground collider and mesh after an edit, splitting off anything that
+    // isn't connected to the bottom of the world anymore into its own dynamic body, cleaned up
+    // by the debris lifetime rules so repeated splits don't pile up
+    fn rebuild_terrain(&mut self) {
+        if let Some(split) = islands::split(&self.csg, GROUND_ANCHOR_Y) {
+            self.csg = split.anchored;
+            for island in split.floating {
+                let collider = self.world.physics.add_convex_csg(
+                    RigidBodyBuilder::new_dynamic().build(),
+                    &island,
+                    EntityKind::Debris,
+                );
+                self.world.debris.track(&mut self.world.physics, collider);
+                self.add_debris(collider, island);
+            }
+        }
+
+        self.ground_handle = self
+            .world
+            .physics
+            .swap_collider(self.ground_handle, &self.csg)
+            .unwrap();
+        self.upload_terrain();
+    }
+
+    // drops the body again if its mesh can't be created rather than simulating something unseen
+    fn add_debris(&mut self, collider: ColliderHandle, csg: rscsg::dim3::Csg) {
+        let vertices = csg.iter_triangles().flat_map(to_vert).collect::<Vec<_>>();
+        match solstice::mesh::VertexMesh::with_data(&mut self.ctx, &vertices) {
+            Ok(geometry) => self.debris.push(Debris {
+                collider,
+                geometry,
+                vert_count: vertices.len(),
+                csg,
+            }),
+            Err(_) => {
+                self.world.physics.remove_body(collider);
+            }
+        }
+    }
+
+    fn upload_terrain(&mut self) {
+        let vertices = self
+            .csg
+            .iter_triangles()
+            .flat_map(to_vert)
+            .collect::<Vec<_>>();
+        self.geometry.set_vertices(&mut self.ctx, &vertices, 0);
+        self.vert_count = vertices.len();
+    }
+
+    // builds meshes for newly spawned props and drops the ones, debris included, that have
+    // been despawned
+    fn sync_prop_meshes(&mut self) {
+        let physics = &self.world.physics;
+        self.prop_meshes
+            .retain(|handle, _| physics.collider(*handle).is_some());
+        self.debris
+            .retain(|debris| physics.collider(debris.collider).is_some());
+
+        for (handle, collider) in physics.entities(EntityKind::Prop) {
+            if self.prop_meshes.contains_key(&handle) {
+                continue;
+            }
+            let vertices = shape_vertices(collider.shape());
+            if vertices.is_empty() {
+                continue;
+            }
+            if let Ok(geometry) = solstice::mesh::VertexMesh::with_data(&mut self.ctx, &vertices) {
+                self.prop_meshes.insert(
+                    handle,
+                    PropMesh {
+                        geometry,
+                        vert_count: vertices.len(),
+                    },
+                );
+            }
+        }
+    }
+
     pub fn handle_resize(&mut self, width: f32, height: f32) {
         self.ctx.set_viewport(0, 0, width as _, height as _);
         self.gfx.set_width_height(width, height);
     }
 }
 
+fn spawn_interval(config: &SpawnerConfig) -> std::time::Duration {
+    std::time::Duration::from_secs_f32(config.interval.max(MIN_SPAWN_INTERVAL))
+}
+
+// flat shaded triangles for the shapes props can have
+fn shape_vertices(shape: &dyn Shape) -> Vec<solstice_2d::Vertex3D> {
+    let (vertices, indices) = if let Some(ball) = shape.as_ball() {
+        ball.to_trimesh(10, 10)
+    } else if let Some(cuboid) = shape.as_cuboid() {
+        cuboid.to_trimesh()
+    } else if let Some(capsule) = shape.as_capsule() {
+        capsule.to_trimesh(20, 20)
+    } else if let Some(polyhedron) = shape.as_convex_polyhedron() {
+        polyhedron.to_trimesh()
+    } else {
+        return vec![];
+    };
+
+    indices
+        .into_iter()
+        .flat_map(|[a, b, c]| {
+            let (a, b, c) = (
+                vertices[a as usize],
+                vertices[b as usize],
+                vertices[c as usize],
+            );
+            let normal = (b - a).cross(&(c - a)).normalize();
+            std::array::IntoIter::new([a, b, c]).map(move |p| solstice_2d::Vertex3D {
+                position: [p.x, p.y, p.z],
+                uv: [0., 0.],
+                color: [1., 1., 1., 1.],
+                normal: [normal.x, normal.y, normal.z],
+            })
+        })
+        .collect()
+}
+
 fn iso_into_tx(position: &rapier3d::math::Isometry<f32>) -> Transform3D {
     use solstice_2d::Rad;
     let v = &position.translation.vector;
@@ -465,46 +1202,317 @@ mod camera {
 }
 
 mod physics {
-    use rapier3d::dynamics::{CCDSolver, IntegrationParameters, JointSet, RigidBody, RigidBodySet};
+    use crate::entity::{EntityKind, DIGGER_FLAG};
+    use crate::fluids::FluidVolume;
+    use crate::gravity::{self, GravityZone};
+    use crate::layers::{CollisionMatrix, Layer, LayerMask};
+    use crate::triggers::{Trigger, TriggerEvent};
+    use crate::trimesh::{weld_vertices, TriMesh, TriMeshDispatcher};
+    use rapier3d::dynamics::{
+        BallJoint, CCDSolver, FixedJoint, IntegrationParameters, JointHandle, JointParams,
+        JointSet, PrismaticJoint, RevoluteJoint, RigidBody, RigidBodyBuilder, RigidBodyHandle,
+        RigidBodySet,
+    };
     use rapier3d::geometry::{
-        BroadPhase, Collider, ColliderBuilder, ColliderHandle, ColliderSet, InteractionGroups,
-        NarrowPhase, Ray,
+        Ball, BroadPhase, Collider, ColliderBuilder, ColliderHandle, ColliderSet, ContactEvent,
+        Cuboid, IntersectionEvent, NarrowPhase, PointProjection, Ray, RayIntersection, Shape,
+        SharedShape, AABB, TOI,
     };
-    use rapier3d::math::Isometry;
-    use rapier3d::na::Vector3;
-    use rapier3d::pipeline::{PhysicsPipeline, QueryPipeline};
+    use rapier3d::math::{Isometry, Point};
+    use rapier3d::na::{Unit, Vector3};
+    use rapier3d::parry::query::{DefaultQueryDispatcher, QueryDispatcher};
+    use rapier3d::pipeline::{EventHandler, PhysicsPipeline, QueryPipeline};
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub enum CollisionEventKind {
+        ContactStarted,
+        ContactStopped,
+        IntersectionStarted,
+        IntersectionStopped,
+    }
 
-    pub struct PhysicsContext {
-        pipeline: PhysicsPipeline,
+    #[derive(Copy, Clone, Debug)]
+    pub struct CollisionEvent {
+        pub kind: CollisionEventKind,
+        pub collider1: ColliderHandle,
+        pub collider2: ColliderHandle,
+        /// `None` if the collider is untagged or has been removed since.
+        pub entity1: Option<EntityKind>,
+        pub entity2: Option<EntityKind>,
+        /// The total impulse applied between both colliders during the step the contact
+        /// started. Always zero for every other kind of event.
+        pub impulse: f32,
+        /// Where most of that impulse was applied, in world space. Only set for contacts that
+        /// just started.
+        pub point: Option<Point<f32>>,
+    }
+
+    impl CollisionEvent {
+        /// The collider on the other side of the event from `handle` along with its kind, or
+        /// `None` if `handle` isn't involved.
+        pub fn other(
+            &self,
+            handle: ColliderHandle,
+        ) -> Option<(ColliderHandle, Option<EntityKind>)> {
+            if self.collider1 == handle {
+                Some((self.collider2, self.entity2))
+            } else if self.collider2 == handle {
+                Some((self.collider1, self.entity1))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct SpringHandle(u32);
+
+    // rapier has no springs of its own so these are applied as forces before every step
+    struct SpringJoint {
+        body1: RigidBodyHandle,
+        anchor1: Point<f32>,
+        body2: RigidBodyHandle,
+        anchor2: Point<f32>,
+        stiffness: f32,
+        damping: f32,
+    }
+
+    /// Narrows down what a query can hit.
+    #[derive(Copy, Clone)]
+    pub struct QueryFilter<'a> {
+        pub layers: LayerMask,
+        /// Only colliders this returns true for are hit.
+        pub predicate: Option<QueryPredicate<'a>>,
+    }
+
+    impl<'a> QueryFilter<'a> {
+        pub fn predicate(self, predicate: QueryPredicate<'a>) -> Self {
+            Self {
+                predicate: Some(predicate),
+                ..self
+            }
+        }
+    }
+
+    impl Default for QueryFilter<'_> {
+        fn default() -> Self {
+            Self {
+                layers: LayerMask::ALL,
+                predicate: None,
+            }
+        }
+    }
+
+    impl From<LayerMask> for QueryFilter<'_> {
+        fn from(layers: LayerMask) -> Self {
+            Self {
+                layers,
+                predicate: None,
+            }
+        }
+    }
+
+    impl From<Layer> for QueryFilter<'_> {
+        fn from(layer: Layer) -> Self {
+            LayerMask::from(layer).into()
+        }
+    }
+
+    /// Collects the events reported by the pipeline so they can be processed once the step is
+    /// over.
+    #[derive(Default)]
+    struct EventCollector {
+        contacts: Mutex<Vec<ContactEvent>>,
+        intersections: Mutex<Vec<IntersectionEvent>>,
+    }
+
+    impl EventHandler for EventCollector {
+        fn handle_intersection_event(&self, event: IntersectionEvent) {
+            self.intersections.lock().unwrap().push(event);
+        }
+
+        fn handle_contact_event(&self, event: ContactEvent) {
+            self.contacts.lock().unwrap().push(event);
+        }
+    }
+
+    /// Only colliders this returns true for are considered by a query.
+    pub type QueryPredicate<'a> = &'a dyn Fn(ColliderHandle, &Collider) -> bool;
+
+    /// Everything needed to put a `PhysicsContext` back the way it was, handles and cached
+    /// contacts included.
+    ///
+    /// The narrow phase's query dispatcher doesn't go through serde and rapier has no way to
+    /// hand it a new one, so a snapshot read back from bytes collides with the terrain through
+    /// the default dispatcher, which doesn't correct contacts against its internal edges.
+    /// Snapshots kept in memory share the original dispatcher and restore exactly.
+    #[derive(Clone, Serialize, Deserialize)]
+    pub struct PhysicsSnapshot {
         gravity: Vector3<f32>,
         integration_parameters: IntegrationParameters,
         broad_phase: BroadPhase,
         narrow_phase: NarrowPhase,
         bodies: RigidBodySet,
+        #[serde(
+            serialize_with = "serialize_colliders",
+            deserialize_with = "deserialize_colliders"
+        )]
         colliders: ColliderSet,
         joints: JointSet,
-        query_pipeline: QueryPipeline,
-        ccd_solver: CCDSolver,
+        world_body: Option<RigidBodyHandle>,
+        triggers: HashMap<ColliderHandle, Trigger>,
+        body_gravity: HashMap<RigidBodyHandle, Vector3<f32>>,
     }
 
-    impl PhysicsContext {
-        pub fn new(gx: f32, gy: f32, gz: f32) -> Self {
-            Self {
-                pipeline: PhysicsPipeline::new(),
-                gravity: Vector3::new(gx, gy, gz),
-                integration_parameters: Default::default(),
-                broad_phase: BroadPhase::new(),
-                narrow_phase: NarrowPhase::new(),
-                bodies: RigidBodySet::new(),
-                colliders: ColliderSet::new(),
-                joints: JointSet::new(),
-                query_pipeline: Default::default(),
-                ccd_solver: CCDSolver::new(),
+    // Terrain shapes can't be serialized so they're written out as parry's own triangle mesh,
+    // which nothing else uses, and turned back into terrain when read.
+    fn serialize_colliders<S>(colliders: &ColliderSet, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut colliders = colliders.clone();
+        let terrain = colliders
+            .iter()
+            .filter_map(|(handle, collider)| {
+                let trimesh = collider.shape().as_shape::<TriMesh>()?;
+                let indices = (0..trimesh.num_triangles() as u32)
+                    .map(|i| [i * 3, i * 3 + 1, i * 3 + 2])
+                    .collect();
+                Some((handle, trimesh.vertices().to_vec(), indices))
+            })
+            .collect::<Vec<_>>();
+        for (handle, vertices, indices) in terrain {
+            if let Some(collider) = colliders.get_mut(handle) {
+                collider.set_shape(SharedShape::trimesh(vertices, indices));
             }
         }
+        colliders.serialize(serializer)
+    }
 
-        pub fn step(&mut self) {
-            self.pipeline.step(
+    fn deserialize_colliders<'de, D>(deserializer: D) -> Result<ColliderSet, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let mut colliders = ColliderSet::deserialize(deserializer)?;
+        let terrain = colliders
+            .iter()
+            .filter_map(|(handle, collider)| {
+                let trimesh = collider.shape().as_trimesh()?;
+                let vertices = trimesh
+                    .triangles()
+                    .flat_map(|triangle| {
+                        IntoIterator::into_iter([triangle.a, triangle.b, triangle.c])
+                    })
+                    .collect::<Vec<_>>();
+                Some((handle, vertices))
+            })
+            .collect::<Vec<_>>();
+        for (handle, vertices) in terrain {
+            if let Some(collider) = colliders.get_mut(handle) {
+                collider.set_shape(SharedShape::new(TriMesh::new(vertices)));
+            }
+        }
+        Ok(colliders)
+    }
+
+    pub struct PhysicsContext {
+        pipeline: PhysicsPipeline,
+        gravity: Vector3<f32>,
+        integration_parameters: IntegrationParameters,
+        broad_phase: BroadPhase,
+        narrow_phase: NarrowPhase,
+        bodies: RigidBodySet,
+        colliders: ColliderSet,
+        joints: JointSet,
+        query_pipeline: QueryPipeline,
+        ccd_solver: CCDSolver,
+        previous_positions: HashMap<ColliderHandle, Isometry<f32>>,
+        // static body at the origin that joints to the world are attached to
+        world_body: Option<RigidBodyHandle>,
+        collision_matrix: CollisionMatrix,
+        triggers: HashMap<ColliderHandle, Trigger>,
+        trigger_events: Vec<TriggerEvent>,
+        fluids: Vec<FluidVolume>,
+        gravity_zones: Vec<GravityZone>,
+        // bodies that ignore global gravity and zones alike
+        body_gravity: HashMap<RigidBodyHandle, Vector3<f32>>,
+        springs: HashMap<SpringHandle, SpringJoint>,
+        next_spring: u32,
+        event_collector: EventCollector,
+        events: Vec<CollisionEvent>,
+    }
+
+    impl PhysicsContext {
+        pub fn new(gx: f32, gy: f32, gz: f32) -> Self {
+            Self {
+                pipeline: PhysicsPipeline::new(),
+                gravity: Vector3::new(gx, gy, gz),
+                integration_parameters: Default::default(),
+                broad_phase: BroadPhase::new(),
+                narrow_phase: NarrowPhase::with_query_dispatcher(
+                    TriMeshDispatcher.chain(DefaultQueryDispatcher),
+                ),
+                bodies: RigidBodySet::new(),
+                colliders: ColliderSet::new(),
+                joints: JointSet::new(),
+                query_pipeline: QueryPipeline::with_query_dispatcher(
+                    TriMeshDispatcher.chain(DefaultQueryDispatcher),
+                ),
+                ccd_solver: CCDSolver::new(),
+                previous_positions: HashMap::new(),
+                world_body: None,
+                collision_matrix: CollisionMatrix::default(),
+                triggers: HashMap::new(),
+                trigger_events: vec![],
+                fluids: vec![],
+                gravity_zones: vec![],
+                body_gravity: HashMap::new(),
+                springs: HashMap::new(),
+                next_spring: 0,
+                event_collector: EventCollector::default(),
+                events: vec![],
+            }
+        }
+
+        /// Replaces which layers collide with which, for existing colliders too.
+        pub fn set_collision_matrix(&mut self, matrix: CollisionMatrix) {
+            self.collision_matrix = matrix;
+            let handles = self
+                .colliders
+                .iter()
+                .map(|(handle, _)| handle)
+                .collect::<Vec<_>>();
+            for handle in handles {
+                if let Some(collider) = self.colliders.get_mut(handle) {
+                    let layer = Layer::of(EntityKind::from_user_data(collider.user_data));
+                    collider.set_collision_groups(self.collision_matrix.groups(layer));
+                }
+            }
+        }
+
+        /// Sets the duration, in seconds, simulated by every call to `step`.
+        pub fn set_timestep(&mut self, dt: f32) {
+            self.integration_parameters.dt = dt;
+        }
+
+        pub fn step(&mut self) {
+            // taken from the bodies rather than the colliders, which only catch up during the
+            // step, so a body teleported since the last step isn't interpolated across the map
+            let bodies = &self.bodies;
+            self.previous_positions.clear();
+            self.previous_positions
+                .extend(self.colliders.iter().filter_map(|(handle, collider)| {
+                    let body = bodies.get(collider.parent())?;
+                    Some((handle, body.position() * collider.position_wrt_parent()))
+                }));
+
+            self.apply_fluids();
+            self.apply_gravity();
+            self.apply_springs();
+            self.pipeline.step(
                 &self.gravity,
                 &self.integration_parameters,
                 &mut self.broad_phase,
@@ -514,22 +1522,435 @@ mod physics {
                 &mut self.joints,
                 &mut self.ccd_solver,
                 &(),
-                &(),
+                &self.event_collector,
             );
             self.query_pipeline.update(&self.bodies, &self.colliders);
+
+            let contacts = std::mem::take(&mut *self.event_collector.contacts.lock().unwrap());
+            for event in contacts {
+                let event = match event {
+                    ContactEvent::Started(collider1, collider2) => {
+                        let (impulse, point) = self.contact_impulse(collider1, collider2);
+                        let mut event = self.collision_event(
+                            CollisionEventKind::ContactStarted,
+                            collider1,
+                            collider2,
+                            impulse,
+                        );
+                        event.point = point;
+                        event
+                    }
+                    ContactEvent::Stopped(collider1, collider2) => self.collision_event(
+                        CollisionEventKind::ContactStopped,
+                        collider1,
+                        collider2,
+                        0.,
+                    ),
+                };
+                self.events.push(event);
+            }
+
+            let intersections =
+                std::mem::take(&mut *self.event_collector.intersections.lock().unwrap());
+            for event in intersections {
+                let kind = if event.intersecting {
+                    CollisionEventKind::IntersectionStarted
+                } else {
+                    CollisionEventKind::IntersectionStopped
+                };
+                let event = self.collision_event(kind, event.collider1, event.collider2, 0.);
+                if let Some(trigger_event) = self.trigger_event(&event) {
+                    self.trigger_events.push(trigger_event);
+                }
+                self.events.push(event);
+            }
+        }
+
+        /// Adds a volume of fluid that pushes up on and slows down anything inside it.
+        pub fn add_fluid(&mut self, fluid: FluidVolume) {
+            self.fluids.push(fluid);
+        }
+
+        // buoyancy and drag, rapier clears forces after every step so this runs before each one
+        fn apply_fluids(&mut self) {
+            if self.fluids.is_empty() {
+                return;
+            }
+
+            let mut forces = vec![];
+            for (handle, body) in self.bodies.iter() {
+                if !body.is_dynamic() || body.is_sleeping() {
+                    continue;
+                }
+                for collider in body.colliders() {
+                    let collider = match self.colliders.get(*collider) {
+                        Some(collider) if !collider.is_sensor() => collider,
+                        _ => continue,
+                    };
+                    let aabb = collider.compute_aabb();
+                    // at a density of one the mass is the volume
+                    let inv_volume = collider.shape().mass_properties(1.).inv_mass;
+                    let volume = if inv_volume > 0. { 1. / inv_volume } else { 0. };
+                    for fluid in self.fluids.iter() {
+                        if let Some((fraction, center)) = fluid.submerged(&aabb) {
+                            // pushes against whatever gravity the body feels where it's under
+                            let gravity = match self.body_gravity.get(&handle) {
+                                Some(gravity) => *gravity,
+                                None => {
+                                    gravity::gravity_at(&self.gravity, &self.gravity_zones, &center)
+                                }
+                            };
+                            let buoyancy = -gravity * fluid.density * volume * fraction;
+                            let drag = -body.linvel() * fluid.linear_drag * body.mass() * fraction;
+                            let angular_drag =
+                                -body.angvel() * fluid.angular_drag * body.mass() * fraction;
+                            forces.push((handle, buoyancy + drag, center, angular_drag));
+                        }
+                    }
+                }
+            }
+
+            for (handle, force, point, torque) in forces {
+                if let Some(body) = self.bodies.get_mut(handle) {
+                    body.apply_force_at_point(force, point, true);
+                    body.apply_torque(torque, true);
+                }
+            }
+        }
+
+        pub fn add_gravity_zone(&mut self, zone: GravityZone) {
+            self.gravity_zones.push(zone);
         }
 
-        pub fn cast_ray(&self, ray: &Ray) -> Option<(ColliderHandle, f32)> {
+        /// The gravity a body at `point` would feel, ignoring per-body gravity.
+        pub fn gravity_at(&self, point: &Point<f32>) -> Vector3<f32> {
+            gravity::gravity_at(&self.gravity, &self.gravity_zones, point)
+        }
+
+        /// Makes the body `coll` is attached to fall along `gravity` no matter where it is, or
+        /// go back to following the world's gravity with `None`.
+        #[allow(unused)]
+        pub fn set_body_gravity(&mut self, coll: ColliderHandle, gravity: Option<Vector3<f32>>) {
+            let body = match self.colliders.get(coll) {
+                Some(collider) => collider.parent(),
+                None => return,
+            };
+            match gravity {
+                Some(gravity) => self.body_gravity.insert(body, gravity),
+                None => self.body_gravity.remove(&body),
+            };
+        }
+
+        // rapier only knows about global gravity so zones and per-body gravity make up the
+        // difference with a force
+        fn apply_gravity(&mut self) {
+            if self.gravity_zones.is_empty() && self.body_gravity.is_empty() {
+                return;
+            }
+
+            let mut forces = vec![];
+            for (handle, body) in self.bodies.iter() {
+                if !body.is_dynamic() || body.is_sleeping() {
+                    continue;
+                }
+                let gravity = match self.body_gravity.get(&handle) {
+                    Some(gravity) => *gravity,
+                    None => {
+                        let point = Point::from(body.position().translation.vector);
+                        gravity::gravity_at(&self.gravity, &self.gravity_zones, &point)
+                    }
+                };
+                let difference = gravity - self.gravity;
+                if difference != Vector3::zeros() {
+                    forces.push((handle, difference * body.mass() * body.gravity_scale()));
+                }
+            }
+
+            for (handle, force) in forces {
+                if let Some(body) = self.bodies.get_mut(handle) {
+                    body.apply_force(force, true);
+                }
+            }
+        }
+
+        /// Takes every event reported since the last call.
+        pub fn drain_events(&mut self) -> std::vec::Drain<'_, CollisionEvent> {
+            self.events.drain(..)
+        }
+
+        /// Takes every trigger enter and exit reported since the last call.
+        pub fn drain_trigger_events(&mut self) -> std::vec::Drain<'_, TriggerEvent> {
+            self.trigger_events.drain(..)
+        }
+
+        fn trigger_event(&self, event: &CollisionEvent) -> Option<TriggerEvent> {
+            let (trigger_handle, other, other_kind) =
+                if self.triggers.contains_key(&event.collider1) {
+                    (event.collider1, event.collider2, event.entity2)
+                } else {
+                    (event.collider2, event.collider1, event.entity1)
+                };
+            let trigger = self.triggers.get(&trigger_handle)?;
+            let other_kind = other_kind.filter(|kind| trigger.watches.contains(kind))?;
+            Some(TriggerEvent {
+                kind: trigger.kind,
+                trigger: trigger_handle,
+                other,
+                other_kind,
+                entered: event.kind == CollisionEventKind::IntersectionStarted,
+            })
+        }
+
+        fn collision_event(
+            &self,
+            kind: CollisionEventKind,
+            collider1: ColliderHandle,
+            collider2: ColliderHandle,
+            impulse: f32,
+        ) -> CollisionEvent {
+            CollisionEvent {
+                kind,
+                collider1,
+                collider2,
+                entity1: self.entity_kind(collider1),
+                entity2: self.entity_kind(collider2),
+                impulse,
+                point: None,
+            }
+        }
+
+        // the total impulse between two colliders and the point the largest part of it went
+        // through
+        fn contact_impulse(
+            &self,
+            collider1: ColliderHandle,
+            collider2: ColliderHandle,
+        ) -> (f32, Option<Point<f32>>) {
+            let pair = match self.narrow_phase.contact_pair(collider1, collider2) {
+                Some(pair) => pair,
+                None => return (0., None),
+            };
+            let position = match self.colliders.get(pair.pair.collider1) {
+                Some(collider) => collider.position(),
+                None => return (0., None),
+            };
+
+            let mut total = 0.;
+            let mut strongest: Option<(f32, Point<f32>)> = None;
+            for point in pair
+                .manifolds
+                .iter()
+                .flat_map(|manifold| manifold.points.iter())
+            {
+                total += point.data.impulse;
+                if strongest.is_none_or(|(impulse, _)| point.data.impulse > impulse) {
+                    strongest = Some((point.data.impulse, position * point.local_p1));
+                }
+            }
+            (total, strongest.map(|(_, point)| point))
+        }
+
+        pub fn cast_ray(&self, ray: &Ray, filter: QueryFilter) -> Option<(ColliderHandle, f32)> {
             self.query_pipeline.cast_ray(
                 &self.colliders,
                 ray,
                 f32::MAX,
                 true,
-                InteractionGroups::all(),
-                None,
+                filter.layers.query_groups(),
+                filter.predicate,
+            )
+        }
+
+        /// Casts `shape` along `shape_vel` and returns the first collider it hits.
+        pub fn cast_shape(
+            &self,
+            shape_pos: &Isometry<f32>,
+            shape_vel: &Vector3<f32>,
+            shape: &dyn Shape,
+            max_toi: f32,
+            filter: QueryFilter,
+        ) -> Option<(ColliderHandle, TOI)> {
+            self.query_pipeline.cast_shape(
+                &self.colliders,
+                shape_pos,
+                shape_vel,
+                shape,
+                max_toi,
+                filter.layers.query_groups(),
+                filter.predicate,
+            )
+        }
+
+        pub fn cast_ray_and_get_normal(
+            &self,
+            ray: &Ray,
+            max_toi: f32,
+            filter: QueryFilter,
+        ) -> Option<(ColliderHandle, RayIntersection)> {
+            self.query_pipeline.cast_ray_and_get_normal(
+                &self.colliders,
+                ray,
+                max_toi,
+                true,
+                filter.layers.query_groups(),
+                filter.predicate,
+            )
+        }
+
+        /// Every hit along `ray` up to `max_toi`, closest first.
+        #[allow(unused)]
+        pub fn cast_ray_all(
+            &self,
+            ray: &Ray,
+            max_toi: f32,
+            filter: QueryFilter,
+        ) -> Vec<(ColliderHandle, RayIntersection)> {
+            let mut hits = vec![];
+            self.query_pipeline.intersections_with_ray(
+                &self.colliders,
+                ray,
+                max_toi,
+                true,
+                filter.layers.query_groups(),
+                filter.predicate,
+                |handle, _collider, intersection| {
+                    hits.push((handle, intersection));
+                    true
+                },
+            );
+            hits.sort_by(|(_, a), (_, b)| {
+                a.toi
+                    .partial_cmp(&b.toi)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            hits
+        }
+
+        /// Whether nothing passing `filter` lies on the segment between `from` and `to`.
+        #[allow(unused)]
+        pub fn has_line_of_sight(
+            &self,
+            from: &Point<f32>,
+            to: &Point<f32>,
+            filter: QueryFilter,
+        ) -> bool {
+            let delta = to - from;
+            let distance = delta.norm();
+            if distance <= f32::EPSILON {
+                return true;
+            }
+            let ray = Ray::new(*from, delta / distance);
+            self.query_pipeline
+                .cast_ray(
+                    &self.colliders,
+                    &ray,
+                    distance,
+                    true,
+                    filter.layers.query_groups(),
+                    filter.predicate,
+                )
+                .is_none()
+        }
+
+        /// The point on the closest collider nearest to `point`. With `solid`, points inside a
+        /// collider project onto themselves.
+        #[allow(unused)]
+        pub fn project_point(
+            &self,
+            point: &Point<f32>,
+            solid: bool,
+            filter: QueryFilter,
+        ) -> Option<(ColliderHandle, PointProjection)> {
+            self.query_pipeline.project_point(
+                &self.colliders,
+                point,
+                solid,
+                filter.layers.query_groups(),
+                filter.predicate,
             )
         }
 
+        /// Every collider that overlaps `shape` placed at `shape_pos`.
+        pub fn intersections_with_shape(
+            &self,
+            shape_pos: &Isometry<f32>,
+            shape: &dyn Shape,
+            filter: QueryFilter,
+        ) -> Vec<ColliderHandle> {
+            let mut intersections = vec![];
+            self.query_pipeline.intersections_with_shape(
+                &self.colliders,
+                shape_pos,
+                shape,
+                filter.layers.query_groups(),
+                filter.predicate,
+                |handle, _collider| {
+                    intersections.push(handle);
+                    true
+                },
+            );
+            intersections
+        }
+
+        pub fn intersections_with_sphere(
+            &self,
+            center: &Point<f32>,
+            radius: f32,
+            filter: QueryFilter,
+        ) -> Vec<ColliderHandle> {
+            let position = Isometry::translation(center.x, center.y, center.z);
+            self.intersections_with_shape(&position, &Ball::new(radius), filter)
+        }
+
+        #[allow(unused)]
+        pub fn intersections_with_aabb(
+            &self,
+            aabb: &AABB,
+            filter: QueryFilter,
+        ) -> Vec<ColliderHandle> {
+            let center = aabb.center();
+            let position = Isometry::translation(center.x, center.y, center.z);
+            self.intersections_with_shape(&position, &Cuboid::new(aabb.half_extents()), filter)
+        }
+
+        /// Pushes every dynamic body whose center of mass is within `radius` of `center` away
+        /// from it, with an impulse of `strength` at the center that falls off linearly to
+        /// nothing at `radius`.
+        pub fn apply_radial_impulse(&mut self, center: &Point<f32>, radius: f32, strength: f32) {
+            for (_handle, body) in self.bodies.iter_mut() {
+                if !body.is_dynamic() {
+                    continue;
+                }
+                let com = body.position() * body.mass_properties().local_com;
+                let delta = com - center;
+                let distance = delta.norm();
+                if distance >= radius {
+                    continue;
+                }
+                // straight up from right underneath it
+                let direction = if distance > f32::EPSILON {
+                    delta / distance
+                } else {
+                    Vector3::y()
+                };
+                body.apply_impulse(direction * strength * (1. - distance / radius), true);
+            }
+        }
+
+        /// Whether `coll` was tagged to carve the terrain where it hits it.
+        pub fn is_digger(&self, coll: ColliderHandle) -> bool {
+            self.colliders
+                .get(coll)
+                .is_some_and(|collider| collider.user_data & DIGGER_FLAG != 0)
+        }
+
+        /// Whether `collider` is attached to a dynamic body, handy as a query filter.
+        pub fn is_dynamic(&self, collider: &Collider) -> bool {
+            self.bodies
+                .get(collider.parent())
+                .is_some_and(RigidBody::is_dynamic)
+        }
+
         pub fn swap_collider(
             &mut self,
             collider: ColliderHandle,
@@ -538,44 +1959,353 @@ mod physics {
             let body = self.colliders.get(collider)?.parent();
             self.colliders.remove(collider, &mut self.bodies, true);
             let collider = Self::build_csg_collider(csg);
-            Some(self.colliders.insert(collider, body, &mut self.bodies))
+            Some(self.insert_collider(collider, body))
+        }
+
+        /// Copies everything that gets simulated, see `PhysicsSnapshot`.
+        pub fn snapshot(&self) -> PhysicsSnapshot {
+            PhysicsSnapshot {
+                gravity: self.gravity,
+                integration_parameters: self.integration_parameters,
+                broad_phase: self.broad_phase.clone(),
+                narrow_phase: self.narrow_phase.clone(),
+                bodies: self.bodies.clone(),
+                colliders: self.colliders.clone(),
+                joints: self.joints.clone(),
+                world_body: self.world_body,
+                triggers: self.triggers.clone(),
+                body_gravity: self.body_gravity.clone(),
+            }
+        }
+
+        /// Replaces everything with the contents of `snapshot`, keeping the collision matrix,
+        /// fluids and gravity zones.
+        ///
+        /// Handles are the same as when the snapshot was taken. Spring joints aren't part of
+        /// snapshots and are all removed.
+        pub fn restore(&mut self, snapshot: &PhysicsSnapshot) {
+            let mut restored = Self::new(0., 0., 0.);
+            restored.collision_matrix = self.collision_matrix.clone();
+            restored.fluids = std::mem::take(&mut self.fluids);
+            restored.gravity_zones = std::mem::take(&mut self.gravity_zones);
+            restored.gravity = snapshot.gravity;
+            restored.integration_parameters = snapshot.integration_parameters;
+            restored.broad_phase = snapshot.broad_phase.clone();
+            restored.narrow_phase = snapshot.narrow_phase.clone();
+            restored.bodies = snapshot.bodies.clone();
+            restored.colliders = snapshot.colliders.clone();
+            restored.joints = snapshot.joints.clone();
+            restored.world_body = snapshot.world_body;
+            restored.triggers = snapshot.triggers.clone();
+            restored.body_gravity = snapshot.body_gravity.clone();
+            restored
+                .query_pipeline
+                .update(&restored.bodies, &restored.colliders);
+
+            *self = restored;
+        }
+
+        /// Joins two bodies at a point, leaving them free to rotate around it.
+        ///
+        /// Anchors are local to each body. Passing `None` instead of a collider attaches that
+        /// end to the world, where its anchor is in world space. Returns `None` if either
+        /// collider doesn't exist.
+        pub fn add_ball_joint(
+            &mut self,
+            coll1: Option<ColliderHandle>,
+            anchor1: Point<f32>,
+            coll2: Option<ColliderHandle>,
+            anchor2: Point<f32>,
+        ) -> Option<JointHandle> {
+            self.insert_joint(coll1, coll2, BallJoint::new(anchor1, anchor2))
+        }
+
+        /// Welds two bodies together so that `frame1` and `frame2` always line up.
+        #[allow(unused)]
+        pub fn add_fixed_joint(
+            &mut self,
+            coll1: Option<ColliderHandle>,
+            frame1: Isometry<f32>,
+            coll2: Option<ColliderHandle>,
+            frame2: Isometry<f32>,
+        ) -> Option<JointHandle> {
+            self.insert_joint(coll1, coll2, FixedJoint::new(frame1, frame2))
+        }
+
+        /// Lets two bodies slide along a shared axis without rotating.
+        #[allow(unused)]
+        pub fn add_prismatic_joint(
+            &mut self,
+            coll1: Option<ColliderHandle>,
+            anchor1: Point<f32>,
+            axis1: Unit<Vector3<f32>>,
+            coll2: Option<ColliderHandle>,
+            anchor2: Point<f32>,
+            axis2: Unit<Vector3<f32>>,
+        ) -> Option<JointHandle> {
+            let joint = PrismaticJoint::new(
+                anchor1,
+                axis1,
+                orthogonal(&axis1),
+                anchor2,
+                axis2,
+                orthogonal(&axis2),
+            );
+            self.insert_joint(coll1, coll2, joint)
+        }
+
+        /// Limits how far apart, along their axis, the anchors of a prismatic joint can get.
+        /// Does nothing for any other kind of joint.
+        #[allow(unused)]
+        pub fn set_prismatic_limits(&mut self, joint: JointHandle, min: f32, max: f32) {
+            if let Some(joint) = self.joints.get_mut(joint) {
+                if let JointParams::PrismaticJoint(prismatic) = &mut joint.params {
+                    prismatic.limits_enabled = true;
+                    prismatic.limits = [min, max];
+                }
+            }
+        }
+
+        /// Hinges two bodies together, leaving them free to rotate around a shared axis.
+        pub fn add_revolute_joint(
+            &mut self,
+            coll1: Option<ColliderHandle>,
+            anchor1: Point<f32>,
+            axis1: Unit<Vector3<f32>>,
+            coll2: Option<ColliderHandle>,
+            anchor2: Point<f32>,
+            axis2: Unit<Vector3<f32>>,
+        ) -> Option<JointHandle> {
+            self.insert_joint(
+                coll1,
+                coll2,
+                RevoluteJoint::new(anchor1, axis1, anchor2, axis2),
+            )
+        }
+
+        #[allow(unused)]
+        pub fn remove_joint(&mut self, joint: JointHandle) {
+            self.joints.remove(joint, &mut self.bodies, true);
+        }
+
+        /// Pulls two bodies together at a point like a damped spring, letting them stretch
+        /// apart under load.
+        ///
+        /// Anchors work the same as for `add_ball_joint`. `stiffness` is the force per unit of
+        /// stretch and `damping` the force per unit of relative velocity, both per unit of
+        /// mass of the body being pulled so heavy bodies follow as readily as light ones.
+        pub fn add_spring_joint(
+            &mut self,
+            coll1: Option<ColliderHandle>,
+            anchor1: Point<f32>,
+            coll2: Option<ColliderHandle>,
+            anchor2: Point<f32>,
+            stiffness: f32,
+            damping: f32,
+        ) -> Option<SpringHandle> {
+            let body1 = self.joint_body(coll1)?;
+            let body2 = self.joint_body(coll2)?;
+            let handle = SpringHandle(self.next_spring);
+            self.next_spring += 1;
+            self.springs.insert(
+                handle,
+                SpringJoint {
+                    body1,
+                    anchor1,
+                    body2,
+                    anchor2,
+                    stiffness,
+                    damping,
+                },
+            );
+            Some(handle)
+        }
+
+        pub fn remove_spring_joint(&mut self, spring: SpringHandle) {
+            self.springs.remove(&spring);
+        }
+
+        // springs whose bodies have been removed go along with them
+        fn apply_springs(&mut self) {
+            let bodies = &self.bodies;
+            self.springs
+                .retain(|_, spring| bodies.contains(spring.body1) && bodies.contains(spring.body2));
+
+            let mut forces = vec![];
+            for spring in self.springs.values() {
+                let (body1, body2) =
+                    match (self.bodies.get(spring.body1), self.bodies.get(spring.body2)) {
+                        (Some(body1), Some(body2)) => (body1, body2),
+                        _ => continue,
+                    };
+                let point1 = body1.position() * spring.anchor1;
+                let point2 = body2.position() * spring.anchor2;
+                let acceleration = (point1 - point2) * spring.stiffness
+                    + (velocity_at(body1, &point1) - velocity_at(body2, &point2)) * spring.damping;
+                if body1.is_dynamic() {
+                    forces.push((spring.body1, -acceleration * body1.mass(), point1));
+                }
+                if body2.is_dynamic() {
+                    forces.push((spring.body2, acceleration * body2.mass(), point2));
+                }
+            }
+
+            for (handle, force, point) in forces {
+                if let Some(body) = self.bodies.get_mut(handle) {
+                    body.apply_force_at_point(force, point, true);
+                }
+            }
+        }
+
+        fn insert_joint<J: Into<JointParams>>(
+            &mut self,
+            coll1: Option<ColliderHandle>,
+            coll2: Option<ColliderHandle>,
+            joint: J,
+        ) -> Option<JointHandle> {
+            let body1 = self.joint_body(coll1)?;
+            let body2 = self.joint_body(coll2)?;
+            Some(self.joints.insert(&mut self.bodies, body1, body2, joint))
+        }
+
+        fn joint_body(&mut self, coll: Option<ColliderHandle>) -> Option<RigidBodyHandle> {
+            match coll {
+                Some(coll) => Some(self.colliders.get(coll)?.parent()),
+                None => {
+                    let bodies = &mut self.bodies;
+                    let body = *self.world_body.get_or_insert_with(|| {
+                        bodies.insert(RigidBodyBuilder::new_static().build())
+                    });
+                    Some(body)
+                }
+            }
+        }
+
+        /// Removes the body `coll` is attached to along with all of its colliders and joints.
+        pub fn remove_body(&mut self, coll: ColliderHandle) -> Option<RigidBody> {
+            let body = self.colliders.get(coll)?.parent();
+            let removed = self
+                .bodies
+                .remove(body, &mut self.colliders, &mut self.joints)?;
+            self.body_gravity.remove(&body);
+            for handle in removed.colliders() {
+                self.previous_positions.remove(handle);
+                self.triggers.remove(handle);
+            }
+            Some(removed)
+        }
+
+        /// Adds a static sensor box centered on `position` that reports the entities it's
+        /// watching for through `drain_trigger_events`.
+        pub fn add_trigger(
+            &mut self,
+            trigger: Trigger,
+            position: &Point<f32>,
+            half_extents: &Vector3<f32>,
+        ) -> ColliderHandle {
+            let body = RigidBodyBuilder::new_static()
+                .translation(position.x, position.y, position.z)
+                .build();
+            let collider = ColliderBuilder::cuboid(half_extents.x, half_extents.y, half_extents.z)
+                .sensor(true)
+                .user_data(EntityKind::Trigger.user_data())
+                .build();
+            let handle = self.add_body(body, collider);
+            self.triggers.insert(handle, trigger);
+            handle
         }
 
         pub fn add_body(&mut self, body: RigidBody, collider: Collider) -> ColliderHandle {
             let body = self.bodies.insert(body);
+            self.insert_collider(collider, body)
+        }
+
+        // every collider goes through here so it ends up on its entity's layer
+        fn insert_collider(
+            &mut self,
+            mut collider: Collider,
+            body: RigidBodyHandle,
+        ) -> ColliderHandle {
+            let layer = Layer::of(EntityKind::from_user_data(collider.user_data));
+            collider.set_collision_groups(self.collision_matrix.groups(layer));
             self.colliders.insert(collider, body, &mut self.bodies)
         }
 
         pub fn add_csg(&mut self, body: RigidBody, csg: &rscsg::dim3::Csg) -> ColliderHandle {
             let collider = Self::build_csg_collider(csg);
             let parent_handle = self.bodies.insert(body);
-            self.colliders
-                .insert(collider, parent_handle, &mut self.bodies)
+            self.insert_collider(collider, parent_handle)
         }
 
-        fn build_csg_collider(csg: &rscsg::dim3::Csg) -> Collider {
-            let vertices = csg
-                .iter_triangles()
-                .flat_map(|triangle| {
-                    std::array::IntoIter::new(triangle.positions).flat_map(|point| {
-                        use rapier3d::math::Point;
-                        std::array::IntoIter::new([Point::new(point.0, point.1, point.2)])
-                    })
-                })
+        /// Adds `csg` as a convex decomposition so it can be simulated as a dynamic body.
+        pub fn add_convex_csg(
+            &mut self,
+            body: RigidBody,
+            csg: &rscsg::dim3::Csg,
+            kind: EntityKind,
+        ) -> ColliderHandle {
+            let vertices = Self::csg_vertices(csg);
+            let (ids, num_vertices) = weld_vertices(&vertices);
+            let mut points = vec![Point::origin(); num_vertices];
+            for (vertex, id) in vertices.iter().zip(ids.iter()) {
+                points[*id as usize] = *vertex;
+            }
+            let indices = ids
+                .chunks_exact(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]])
                 .collect::<Vec<_>>();
-            let indices = csg
-                .iter_triangles()
-                .enumerate()
-                .map(|(i, _triangle)| {
-                    let i = i as u32 * 3;
-                    [i + 0, i + 1, i + 2]
+
+            let collider = ColliderBuilder::convex_decomposition(&points, &indices)
+                .user_data(kind.user_data())
+                .build();
+            self.add_body(body, collider)
+        }
+
+        pub fn csg_vertices(csg: &rscsg::dim3::Csg) -> Vec<Point<f32>> {
+            csg.iter_triangles()
+                .flat_map(|triangle| {
+                    std::array::IntoIter::new(triangle.positions)
+                        .map(|point| Point::new(point.0, point.1, point.2))
                 })
-                .collect::<Vec<[u32; 3]>>();
-            ColliderBuilder::trimesh(vertices, indices).build()
+                .collect()
+        }
+
+        fn build_csg_collider(csg: &rscsg::dim3::Csg) -> Collider {
+            let vertices = Self::csg_vertices(csg);
+            ColliderBuilder::new(SharedShape::new(TriMesh::new(vertices)))
+                .user_data(EntityKind::Terrain.user_data())
+                .build()
+        }
+
+        /// The position of a collider `alpha` of the way between the previous step and the
+        /// current one.
+        pub fn interpolated_position(
+            &self,
+            coll: ColliderHandle,
+            alpha: f32,
+        ) -> Option<Isometry<f32>> {
+            let current = self.colliders.get(coll)?.position();
+            let previous = self.previous_positions.get(&coll).unwrap_or(current);
+            Some(previous.lerp_slerp(current, alpha))
         }
 
-        pub fn collider_position(&self, coll: ColliderHandle) -> Option<&Isometry<f32>> {
-            self.colliders.get(coll).map(Collider::position)
+        pub fn collider(&self, coll: ColliderHandle) -> Option<&Collider> {
+            self.colliders.get(coll)
+        }
+
+        /// The velocity of the kinematic body `coll` is attached to, `None` for any other kind
+        /// of body.
+        pub fn kinematic_velocity(&self, coll: ColliderHandle) -> Option<Vector3<f32>> {
+            let body = self.bodies.get(self.colliders.get(coll)?.parent())?;
+            if body.is_kinematic() {
+                Some(*body.linvel())
+            } else {
+                None
+            }
+        }
+
+        pub fn rigid_body(&self, coll: ColliderHandle) -> Option<&RigidBody> {
+            self.bodies.get(self.colliders.get(coll)?.parent())
         }
 
         pub fn rigid_body_mut(&mut self, coll: ColliderHandle) -> Option<&mut RigidBody> {
@@ -584,20 +2314,511 @@ mod physics {
             Some(body)
         }
 
-        pub fn colliders(&self) -> impl std::iter::Iterator<Item = &Collider> + '_ {
-            self.colliders.iter().filter_map(|(_handle, collider)| {
-                if collider.user_data == 1 {
-                    Some(collider)
-                } else {
-                    None
-                }
+        pub fn iter_colliders(
+            &self,
+        ) -> impl std::iter::Iterator<Item = (ColliderHandle, &Collider)> + '_ {
+            self.colliders.iter()
+        }
+
+        /// The world space point and normal of every active contact.
+        pub fn contacts(&self) -> impl std::iter::Iterator<Item = (Point<f32>, Vector3<f32>)> + '_ {
+            self.narrow_phase
+                .contact_pairs()
+                .flat_map(|pair| pair.manifolds.iter())
+                .flat_map(|manifold| {
+                    let normal = manifold.data.normal;
+                    manifold
+                        .data
+                        .solver_contacts
+                        .iter()
+                        .map(move |contact| (contact.point, normal))
+                })
+        }
+
+        pub fn entity_kind(&self, coll: ColliderHandle) -> Option<EntityKind> {
+            EntityKind::from_user_data(self.colliders.get(coll)?.user_data)
+        }
+
+        /// Every collider tagged as `kind`.
+        pub fn entities(
+            &self,
+            kind: EntityKind,
+        ) -> impl std::iter::Iterator<Item = (ColliderHandle, &Collider)> + '_ {
+            self.colliders.iter().filter(move |(_handle, collider)| {
+                EntityKind::from_user_data(collider.user_data) == Some(kind)
             })
         }
     }
+
+    // the velocity of the point of `body` currently at `point`
+    fn velocity_at(body: &RigidBody, point: &Point<f32>) -> Vector3<f32> {
+        let com = body.position() * body.mass_properties().local_com;
+        body.linvel() + body.angvel().cross(&(point - com))
+    }
+
+    // any unit vector perpendicular to `axis`
+    fn orthogonal(axis: &Unit<Vector3<f32>>) -> Vector3<f32> {
+        let other = if axis.x.abs() < 0.9 {
+            Vector3::x()
+        } else {
+            Vector3::y()
+        };
+        axis.cross(&other).normalize()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::fluids::FluidBounds;
+        use crate::gravity::{GravityField, GravityMode};
+
+        const EPSILON: f32 = 1.0e-4;
+
+        fn ball(
+            physics: &mut PhysicsContext,
+            position: Point<f32>,
+            density: f32,
+        ) -> ColliderHandle {
+            physics.add_body(
+                RigidBodyBuilder::new_dynamic()
+                    .translation(position.x, position.y, position.z)
+                    .build(),
+                ColliderBuilder::ball(0.5).density(density).build(),
+            )
+        }
+
+        fn linvel(physics: &PhysicsContext, coll: ColliderHandle) -> Vector3<f32> {
+            *physics.rigid_body(coll).unwrap().linvel()
+        }
+
+        #[test]
+        fn springs_pull_towards_their_anchor() {
+            let mut physics = PhysicsContext::new(0., 0., 0.);
+            let light = ball(&mut physics, Point::new(2., 0., 0.), 1.);
+            let heavy = ball(&mut physics, Point::new(2., 10., 0.), 8.);
+            let anchors = [(light, Point::origin()), (heavy, Point::new(0., 10., 0.))];
+            for &(coll, anchor) in anchors.iter() {
+                physics
+                    .add_spring_joint(None, anchor, Some(coll), Point::origin(), 10., 0.)
+                    .unwrap();
+            }
+            physics.step();
+
+            // stretched by two along x, so both accelerate at 20 back towards the anchor
+            let dt = physics.integration_parameters.dt;
+            let expected = Vector3::new(-20. * dt, 0., 0.);
+            assert!((linvel(&physics, light) - expected).norm() < EPSILON);
+            assert!((linvel(&physics, heavy) - expected).norm() < EPSILON);
+        }
+
+        #[test]
+        fn springs_pull_both_bodies_together() {
+            let mut physics = PhysicsContext::new(0., 0., 0.);
+            let light = ball(&mut physics, Point::new(-1., 0., 0.), 1.);
+            let heavy = ball(&mut physics, Point::new(1., 0., 0.), 8.);
+            physics
+                .add_spring_joint(
+                    Some(light),
+                    Point::origin(),
+                    Some(heavy),
+                    Point::origin(),
+                    10.,
+                    0.,
+                )
+                .unwrap();
+            physics.step();
+
+            let dt = physics.integration_parameters.dt;
+            let expected = Vector3::new(20. * dt, 0., 0.);
+            assert!((linvel(&physics, light) - expected).norm() < EPSILON);
+            assert!((linvel(&physics, heavy) + expected).norm() < EPSILON);
+        }
+
+        #[test]
+        fn springs_go_with_their_bodies() {
+            let mut physics = PhysicsContext::new(0., 0., 0.);
+            let coll = ball(&mut physics, Point::new(2., 0., 0.), 1.);
+            physics
+                .add_spring_joint(None, Point::origin(), Some(coll), Point::origin(), 10., 0.)
+                .unwrap();
+            physics.remove_body(coll);
+            physics.step();
+            assert!(physics.springs.is_empty());
+        }
+
+        #[test]
+        fn teleports_are_not_interpolated() {
+            let mut physics = PhysicsContext::new(0., 0., 0.);
+            let coll = ball(&mut physics, Point::origin(), 1.);
+            physics.step();
+            physics
+                .rigid_body_mut(coll)
+                .unwrap()
+                .set_position(Isometry::translation(10., 0., 0.), true);
+            physics.step();
+
+            let position = physics.interpolated_position(coll, 0.).unwrap();
+            assert!((position.translation.vector - Vector3::new(10., 0., 0.)).norm() < EPSILON);
+        }
+
+        fn position(physics: &PhysicsContext, coll: ColliderHandle) -> Vector3<f32> {
+            physics
+                .rigid_body(coll)
+                .unwrap()
+                .position()
+                .translation
+                .vector
+        }
+
+        fn steps(physics: &mut PhysicsContext, count: usize) {
+            for _ in 0..count {
+                physics.step();
+            }
+        }
+
+        #[test]
+        fn fixed_joints_hold_bodies_in_place() {
+            let mut physics = PhysicsContext::new(0., -9.81, 0.);
+            let coll = ball(&mut physics, Point::new(0., 5., 0.), 1.);
+            physics
+                .add_fixed_joint(
+                    None,
+                    Isometry::translation(0., 5., 0.),
+                    Some(coll),
+                    Isometry::identity(),
+                )
+                .unwrap();
+            steps(&mut physics, 60);
+            assert!((position(&physics, coll) - Vector3::new(0., 5., 0.)).norm() < 0.05);
+        }
+
+        #[test]
+        fn removed_joints_let_go() {
+            let mut physics = PhysicsContext::new(0., -9.81, 0.);
+            let coll = ball(&mut physics, Point::new(0., 5., 0.), 1.);
+            let joint = physics
+                .add_fixed_joint(
+                    None,
+                    Isometry::translation(0., 5., 0.),
+                    Some(coll),
+                    Isometry::identity(),
+                )
+                .unwrap();
+            physics.remove_joint(joint);
+            steps(&mut physics, 30);
+            assert!(position(&physics, coll).y < 4.);
+        }
+
+        #[test]
+        fn prismatic_joints_slide_along_their_axis_within_limits() {
+            let mut physics = PhysicsContext::new(0., -9.81, 0.);
+            let coll = ball(&mut physics, Point::new(0., 5., 0.), 1.);
+            physics
+                .rigid_body_mut(coll)
+                .unwrap()
+                .set_linvel(Vector3::x() * 3., true);
+            let joint = physics
+                .add_prismatic_joint(
+                    None,
+                    Point::new(0., 5., 0.),
+                    Vector3::y_axis(),
+                    Some(coll),
+                    Point::origin(),
+                    Vector3::y_axis(),
+                )
+                .unwrap();
+            physics.set_prismatic_limits(joint, -1., 1.);
+            steps(&mut physics, 120);
+
+            let position = position(&physics, coll);
+            assert!(position.x.abs() < 0.05 && position.z.abs() < 0.05);
+            assert!((position.y - 4.).abs() < 0.05);
+        }
+
+        #[test]
+        fn prismatic_limits_only_change_prismatic_joints() {
+            let mut physics = PhysicsContext::new(0., -9.81, 0.);
+            let coll = ball(&mut physics, Point::new(0., 5., 0.), 1.);
+            let joint = physics
+                .add_ball_joint(None, Point::new(0., 5., 0.), Some(coll), Point::origin())
+                .unwrap();
+            physics.set_prismatic_limits(joint, -1., 1.);
+            assert!(matches!(
+                physics.joints.get(joint).unwrap().params,
+                JointParams::BallJoint(_)
+            ));
+        }
+
+        // unit boxes on the x axis at every `x`, in the query pipeline and ready to be queried
+        fn boxes(xs: &[f32]) -> (PhysicsContext, Vec<ColliderHandle>) {
+            let mut physics = PhysicsContext::new(0., 0., 0.);
+            let handles = xs
+                .iter()
+                .map(|x| {
+                    physics.add_body(
+                        RigidBodyBuilder::new_static()
+                            .translation(*x, 0., 0.)
+                            .build(),
+                        ColliderBuilder::cuboid(0.5, 0.5, 0.5).build(),
+                    )
+                })
+                .collect();
+            physics.step();
+            (physics, handles)
+        }
+
+        #[test]
+        fn cast_ray_all_returns_hits_closest_first() {
+            let (physics, handles) = boxes(&[6., 2., 4.]);
+            let ray = Ray::new(Point::origin(), Vector3::x());
+
+            let hits = physics.cast_ray_all(&ray, 10., QueryFilter::default());
+            let order = hits.iter().map(|(handle, _)| *handle).collect::<Vec<_>>();
+            assert_eq!(order, vec![handles[1], handles[2], handles[0]]);
+            let tois = hits.iter().map(|(_, hit)| hit.toi).collect::<Vec<_>>();
+            for (toi, expected) in tois.iter().zip([1.5, 3.5, 5.5].iter()) {
+                assert!((toi - expected).abs() < EPSILON);
+            }
+
+            assert_eq!(
+                physics.cast_ray_all(&ray, 4., QueryFilter::default()).len(),
+                2
+            );
+        }
+
+        #[test]
+        fn line_of_sight_is_blocked_by_colliders_in_between() {
+            let (physics, handles) = boxes(&[2.]);
+            let from = Point::origin();
+            let filter = QueryFilter::default();
+            assert!(physics.has_line_of_sight(&from, &Point::new(1., 0., 0.), filter));
+            assert!(physics.has_line_of_sight(&from, &from, filter));
+            assert!(!physics.has_line_of_sight(&from, &Point::new(5., 0., 0.), filter));
+
+            let blocker = handles[0];
+            let not_blocker = |handle: ColliderHandle, _: &Collider| handle != blocker;
+            let filter = QueryFilter::default().predicate(&not_blocker);
+            assert!(physics.has_line_of_sight(&from, &Point::new(5., 0., 0.), filter));
+            let filter = QueryFilter::from(Layer::Terrain);
+            assert!(physics.has_line_of_sight(&from, &Point::new(5., 0., 0.), filter));
+        }
+
+        #[test]
+        fn project_point_finds_the_closest_surface() {
+            let (physics, handles) = boxes(&[0., 5.]);
+            let filter = QueryFilter::default();
+
+            let (handle, projection) = physics
+                .project_point(&Point::new(0., 3., 0.), true, filter)
+                .unwrap();
+            assert_eq!(handle, handles[0]);
+            assert!(!projection.is_inside);
+            assert!((projection.point - Point::new(0., 0.5, 0.)).norm() < EPSILON);
+
+            let inside = Point::new(5.1, 0., 0.);
+            let (handle, projection) = physics.project_point(&inside, true, filter).unwrap();
+            assert_eq!(handle, handles[1]);
+            assert!(projection.is_inside);
+            assert!((projection.point - inside).norm() < EPSILON);
+
+            let (_, projection) = physics.project_point(&inside, false, filter).unwrap();
+            assert!((projection.point - Point::new(5.5, 0., 0.)).norm() < EPSILON);
+        }
+
+        #[test]
+        fn intersections_with_aabb_finds_overlapping_colliders() {
+            let (physics, handles) = boxes(&[0., 2., 4.]);
+            let aabb = AABB::new(Point::new(1., -1., -1.), Point::new(3.8, 1., 1.));
+            let mut hits = physics.intersections_with_aabb(&aabb, QueryFilter::default());
+            hits.sort_by_key(|handle| handles.iter().position(|h| h == handle));
+            assert_eq!(hits, vec![handles[1], handles[2]]);
+
+            let aabb = AABB::new(Point::new(0.6, -1., -1.), Point::new(1.4, 1., 1.));
+            assert!(physics
+                .intersections_with_aabb(&aabb, QueryFilter::default())
+                .is_empty());
+        }
+
+        fn pool(physics: &mut PhysicsContext, density: f32) {
+            let bounds = AABB::new(Point::new(-5., -5., -5.), Point::new(5., 5., 5.));
+            let mut fluid = FluidVolume::new(FluidBounds::Aabb(bounds), 5.);
+            fluid.density = density;
+            physics.add_fluid(fluid);
+        }
+
+        #[test]
+        fn buoyancy_pushes_against_gravity() {
+            let mut physics = PhysicsContext::new(0., -10., 0.);
+            pool(&mut physics, 1.5);
+            let coll = ball(&mut physics, Point::origin(), 1.);
+            physics.step();
+            // half again as dense as the ball, so it rises at half of gravity
+            let dt = physics.integration_parameters.dt;
+            assert!((linvel(&physics, coll) - Vector3::new(0., 5. * dt, 0.)).norm() < EPSILON);
+        }
+
+        #[test]
+        fn buoyancy_follows_gravity_zones() {
+            let mut physics = PhysicsContext::new(0., -10., 0.);
+            pool(&mut physics, 1.5);
+            physics.add_gravity_zone(GravityZone {
+                field: GravityField::Directional {
+                    bounds: AABB::new(Point::new(-5., -5., -5.), Point::new(5., 5., 5.)),
+                    acceleration: Vector3::new(10., 0., 0.),
+                },
+                mode: GravityMode::Override,
+            });
+            let coll = ball(&mut physics, Point::origin(), 1.);
+            physics.step();
+            let dt = physics.integration_parameters.dt;
+            assert!((linvel(&physics, coll) - Vector3::new(-5. * dt, 0., 0.)).norm() < EPSILON);
+        }
+
+        #[test]
+        fn fluids_drag_bodies_moving_through_them() {
+            let mut physics = PhysicsContext::new(0., -10., 0.);
+            // as dense as the ball so it neither sinks nor floats
+            pool(&mut physics, 1.);
+            let coll = ball(&mut physics, Point::origin(), 1.);
+            let body = physics.rigid_body_mut(coll).unwrap();
+            body.set_linvel(Vector3::new(2., 0., 0.), true);
+            body.set_angvel(Vector3::new(0., 3., 0.), true);
+            physics.step();
+
+            // linear drag of one per unit of mass takes off a `dt`th of the velocity every step
+            let dt = physics.integration_parameters.dt;
+            let expected = Vector3::new(2. * (1. - dt), 0., 0.);
+            assert!((linvel(&physics, coll) - expected).norm() < EPSILON);
+            let angvel = physics.rigid_body(coll).unwrap().angvel().y;
+            assert!(angvel > 0. && angvel < 3.);
+        }
+
+        #[test]
+        fn balls_come_to_rest_on_csg_terrain() {
+            let mut physics = PhysicsContext::new(0., -9.81, 0.);
+            let floor = rscsg::dim3::Csg::cube(rscsg::dim3::Vector(10., 1., 10.), true);
+            let ground = physics.add_csg(RigidBodyBuilder::new_static().build(), &floor);
+            let ball = ball(&mut physics, Point::new(0., 2., 0.), 1.);
+            for _ in 0..120 {
+                physics.step();
+            }
+
+            let pair = physics.narrow_phase.contact_pair(ground, ball).unwrap();
+            assert!(pair.has_any_active_contact);
+            // resting on the top of the floor at 0.5 rather than sinking into it
+            let body = physics.rigid_body(ball).unwrap();
+            assert!((body.position().translation.y - 1.).abs() < 0.01);
+            assert!(body.linvel().norm() < 0.01);
+        }
+    }
+}
+
+mod timestep {
+    use std::time::Duration;
+
+    /// Accumulates frame time and hands it out in fixed size steps.
+    pub struct FixedTimestep {
+        step: Duration,
+        max_substeps: u32,
+        accumulator: Duration,
+    }
+
+    impl FixedTimestep {
+        pub fn new(hz: f32, max_substeps: u32) -> Self {
+            Self {
+                step: Duration::from_secs_f32(1. / hz),
+                max_substeps,
+                accumulator: Duration::default(),
+            }
+        }
+
+        /// The length of a single step, in seconds.
+        pub fn dt(&self) -> f32 {
+            self.step.as_secs_f32()
+        }
+
+        pub fn set_hz(&mut self, hz: f32) {
+            self.step = Duration::from_secs_f32(1. / hz);
+        }
+
+        #[allow(unused)]
+        pub fn set_max_substeps(&mut self, max_substeps: u32) {
+            self.max_substeps = max_substeps;
+        }
+
+        /// Adds `dt` to the accumulator and returns how many steps should be run this frame.
+        pub fn advance(&mut self, dt: Duration) -> u32 {
+            self.accumulator += dt;
+
+            let mut steps = 0;
+            while self.accumulator >= self.step && steps < self.max_substeps {
+                self.accumulator -= self.step;
+                steps += 1;
+            }
+
+            // we can't keep up so drop the backlog instead of spiralling further behind
+            if self.accumulator >= self.step {
+                let remainder = self.accumulator.as_nanos() % self.step.as_nanos();
+                self.accumulator = Duration::from_nanos(remainder as u64);
+            }
+
+            steps
+        }
+
+        /// How far, from 0 to 1, the accumulated time is between the last step and the next.
+        pub fn alpha(&self) -> f32 {
+            self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const EPSILON: f32 = 1.0e-6;
+
+        // a quarter of a second is exact in both f32 and nanoseconds
+        fn timestep(max_substeps: u32) -> FixedTimestep {
+            FixedTimestep::new(4., max_substeps)
+        }
+
+        #[test]
+        fn runs_a_step_per_whole_step_of_time() {
+            let mut timestep = timestep(10);
+            assert_eq!(timestep.advance(Duration::from_millis(600)), 2);
+            assert_eq!(timestep.advance(Duration::from_millis(400)), 2);
+            assert_eq!(timestep.advance(Duration::from_millis(200)), 0);
+        }
+
+        #[test]
+        fn drops_the_backlog_past_max_substeps() {
+            let mut timestep = timestep(3);
+            assert_eq!(timestep.advance(Duration::from_millis(1100)), 3);
+            // less than a step is kept, the rest dropped
+            assert!((timestep.alpha() - 0.4).abs() < EPSILON);
+            assert_eq!(timestep.advance(Duration::from_millis(100)), 0);
+            assert_eq!(timestep.advance(Duration::from_millis(50)), 1);
+        }
+
+        #[test]
+        fn alpha_is_how_far_into_the_next_step() {
+            let mut timestep = timestep(10);
+            assert_eq!(timestep.alpha(), 0.);
+            timestep.advance(Duration::from_millis(100));
+            assert!((timestep.alpha() - 0.4).abs() < EPSILON);
+            timestep.advance(Duration::from_millis(275));
+            assert!((timestep.alpha() - 0.5).abs() < EPSILON);
+        }
+    }
 }
 
 mod cron {
+    /// Identifies a repeating callback so it can be changed or removed later.
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct JobId(u64);
+
     struct Every<T> {
+        id: JobId,
         t: std::time::Duration,
         running: std::time::Duration,
         callback: Box<dyn FnMut(&mut T)>,
@@ -612,6 +2833,7 @@ mod cron {
 
     pub struct Cron<T> {
         t: std::time::Duration,
+        next_id: u64,
         every_callbacks: Vec<Every<T>>,
         after_callbacks: Vec<After<T>>,
     }
@@ -620,6 +2842,7 @@ mod cron {
         fn default() -> Self {
             Self {
                 t: Default::default(),
+                next_id: 0,
                 every_callbacks: vec![],
                 after_callbacks: vec![],
             }
@@ -627,18 +2850,32 @@ mod cron {
     }
 
     impl<T> Cron<T> {
-        pub fn every<F>(&mut self, t: std::time::Duration, callback: F)
+        pub fn every<F>(&mut self, t: std::time::Duration, callback: F) -> JobId
         where
             F: FnMut(&mut T) + 'static,
         {
+            let id = JobId(self.next_id);
+            self.next_id += 1;
             self.every_callbacks.push(Every {
+                id,
                 t,
                 running: Default::default(),
                 callback: Box::new(callback),
             });
+            id
+        }
+
+        /// Changes how often `id` runs, keeping the time already elapsed towards the next run.
+        pub fn set_interval(&mut self, id: JobId, t: std::time::Duration) {
+            if let Some(every) = self.every_callbacks.iter_mut().find(|every| every.id == id) {
+                every.t = t;
+            }
+        }
+
+        pub fn remove(&mut self, id: JobId) {
+            self.every_callbacks.retain(|every| every.id != id);
         }
 
-        #[allow(unused)]
         pub fn after<F>(&mut self, t: std::time::Duration, callback: F)
         where
             F: FnMut(&mut T) + 'static,
@@ -656,6 +2893,9 @@ mod cron {
             dt: std::time::Duration,
         ) -> impl Iterator<Item = &mut (dyn FnMut(&mut T) + 'static)> + '_ {
             self.t += dt;
+            // callbacks are handed out by reference, so the ones that fired last time are only
+            // dropped now
+            self.after_callbacks.retain(|after| !after.triggered);
 
             self.every_callbacks
                 .iter_mut()
@@ -683,6 +2923,26 @@ mod cron {
                 }))
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::time::Duration;
+
+        #[test]
+        fn after_runs_once_and_is_dropped() {
+            let mut cron = Cron::<u32>::default();
+            cron.after(Duration::from_secs(1), |count| *count += 1);
+            let mut count = 0;
+            for _ in 0..3 {
+                for callback in cron.update(Duration::from_millis(600)) {
+                    callback(&mut count);
+                }
+            }
+            assert_eq!(count, 1);
+            assert!(cron.after_callbacks.is_empty());
+        }
+    }
 }
 
 fn to_vert(triangle: rscsg::dim3::Triangle) -> impl Iterator<Item = solstice_2d::Vertex3D> {
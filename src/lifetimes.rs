@@ -0,0 +1,175 @@
+use crate::physics::PhysicsContext;
+use rapier3d::geometry::ColliderHandle;
+use std::collections::VecDeque;
+
+pub struct LifetimeRules {
+    /// Bodies that fall below this height are removed.
+    pub kill_plane_y: f32,
+    /// Seconds a body is allowed to live for, forever if `None`.
+    pub max_age: Option<f32>,
+    /// How many bodies can be alive at once. Spawning past this removes the oldest ones.
+    pub max_live: usize,
+}
+
+impl Default for LifetimeRules {
+    fn default() -> Self {
+        Self {
+            kill_plane_y: -50.,
+            max_age: Some(60.),
+            max_live: 50,
+        }
+    }
+}
+
+/// Keeps track of when bodies were spawned and removes them once they're out of bounds, too old
+/// or too many.
+pub struct Lifetimes {
+    pub rules: LifetimeRules,
+    // oldest first
+    live: VecDeque<(ColliderHandle, f32)>,
+    time: f32,
+}
+
+impl Lifetimes {
+    pub fn new(rules: LifetimeRules) -> Self {
+        Self {
+            rules,
+            live: VecDeque::new(),
+            time: 0.,
+        }
+    }
+
+    /// How many tracked bodies are currently alive.
+    pub fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    /// Every tracked body along with how many seconds it has been alive, oldest first.
+    pub fn ages(&self) -> Vec<(ColliderHandle, f32)> {
+        self.live
+            .iter()
+            .map(|(handle, spawned_at)| (*handle, self.time - spawned_at))
+            .collect()
+    }
+
+    /// Replaces every tracked body, as returned by `ages`.
+    pub fn restore<I>(&mut self, ages: I)
+    where
+        I: IntoIterator<Item = (ColliderHandle, f32)>,
+    {
+        let time = self.time;
+        self.live = ages
+            .into_iter()
+            .map(|(handle, age)| (handle, time - age))
+            .collect();
+    }
+
+    /// Starts tracking the body `handle` is attached to, recycling the oldest bodies if that
+    /// goes over the cap.
+    pub fn track(&mut self, physics: &mut PhysicsContext, handle: ColliderHandle) {
+        self.live.push_back((handle, self.time));
+        while self.live.len() > self.rules.max_live {
+            if let Some((oldest, _)) = self.live.pop_front() {
+                physics.remove_body(oldest);
+            }
+        }
+    }
+
+    /// Ages every body by `dt` seconds and removes the ones that broke a rule.
+    pub fn update(&mut self, physics: &mut PhysicsContext, dt: f32) {
+        self.time += dt;
+
+        let rules = &self.rules;
+        let time = self.time;
+        let mut expired = vec![];
+        self.live.retain(|&(handle, spawned_at)| {
+            let alive = match physics.collider(handle) {
+                Some(collider) => {
+                    let too_old = rules.max_age.is_some_and(|age| time - spawned_at > age);
+                    let too_low = collider.position().translation.y < rules.kill_plane_y;
+                    !too_old && !too_low
+                }
+                // removed from under us, nothing left to clean up
+                None => return false,
+            };
+            if !alive {
+                expired.push(handle);
+            }
+            alive
+        });
+
+        for handle in expired {
+            physics.remove_body(handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rapier3d::dynamics::RigidBodyBuilder;
+    use rapier3d::geometry::ColliderBuilder;
+
+    fn ball(physics: &mut PhysicsContext, y: f32) -> ColliderHandle {
+        physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .translation(0., y, 0.)
+                .build(),
+            ColliderBuilder::ball(0.5).build(),
+        )
+    }
+
+    fn rules(kill_plane_y: f32, max_age: Option<f32>, max_live: usize) -> LifetimeRules {
+        LifetimeRules {
+            kill_plane_y,
+            max_age,
+            max_live,
+        }
+    }
+
+    #[test]
+    fn removes_bodies_below_the_kill_plane() {
+        let mut physics = PhysicsContext::new(0., 0., 0.);
+        let mut lifetimes = Lifetimes::new(rules(0., None, 10));
+        let above = ball(&mut physics, 1.);
+        let below = ball(&mut physics, -1.);
+        lifetimes.track(&mut physics, above);
+        lifetimes.track(&mut physics, below);
+
+        lifetimes.update(&mut physics, 0.1);
+        assert!(physics.collider(above).is_some());
+        assert!(physics.collider(below).is_none());
+        assert_eq!(lifetimes.len(), 1);
+    }
+
+    #[test]
+    fn removes_bodies_past_max_age() {
+        let mut physics = PhysicsContext::new(0., 0., 0.);
+        let mut lifetimes = Lifetimes::new(rules(-50., Some(1.), 10));
+        let old = ball(&mut physics, 0.);
+        lifetimes.track(&mut physics, old);
+        lifetimes.update(&mut physics, 0.6);
+        let young = ball(&mut physics, 0.);
+        lifetimes.track(&mut physics, young);
+
+        lifetimes.update(&mut physics, 0.6);
+        assert!(physics.collider(old).is_none());
+        assert!(physics.collider(young).is_some());
+        assert_eq!(lifetimes.ages(), vec![(young, 0.6)]);
+    }
+
+    #[test]
+    fn recycles_the_oldest_bodies_over_the_cap() {
+        let mut physics = PhysicsContext::new(0., 0., 0.);
+        let mut lifetimes = Lifetimes::new(rules(-50., None, 2));
+        let handles = (0..4).map(|_| ball(&mut physics, 0.)).collect::<Vec<_>>();
+        for handle in handles.iter() {
+            lifetimes.track(&mut physics, *handle);
+            lifetimes.update(&mut physics, 0.5);
+        }
+
+        assert!(physics.collider(handles[0]).is_none());
+        assert!(physics.collider(handles[1]).is_none());
+        assert_eq!(lifetimes.ages(), vec![(handles[2], 1.), (handles[3], 0.5)]);
+    }
+}
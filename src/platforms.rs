@@ -0,0 +1,238 @@
+use crate::physics::PhysicsContext;
+use rapier3d::geometry::ColliderHandle;
+use rapier3d::math::Isometry;
+use rapier3d::na::{Point3, Vector3};
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    /// Slows down into and speeds up out of every waypoint.
+    SmoothStep,
+}
+
+impl Easing {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::SmoothStep => t * t * (3. - 2. * t),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PathMode {
+    /// Carries on from the last waypoint back to the first.
+    Loop,
+    /// Turns around at either end.
+    PingPong,
+}
+
+/// A spline passing through every waypoint, travelled at a fixed pace.
+pub struct Path {
+    pub waypoints: Vec<Point3<f32>>,
+    /// Seconds spent going from one waypoint to the next.
+    pub segment_duration: f32,
+    pub easing: Easing,
+    pub mode: PathMode,
+}
+
+impl Path {
+    fn segments(&self) -> usize {
+        match self.mode {
+            PathMode::Loop => self.waypoints.len(),
+            PathMode::PingPong => self.waypoints.len().saturating_sub(1),
+        }
+    }
+
+    /// Seconds it takes to get back to the first waypoint.
+    pub fn period(&self) -> f32 {
+        let trip = self.segments() as f32 * self.segment_duration;
+        match self.mode {
+            PathMode::Loop => trip,
+            PathMode::PingPong => trip * 2.,
+        }
+    }
+
+    /// Where along the path something is `time` seconds after leaving the first waypoint.
+    pub fn position_at(&self, time: f32) -> Point3<f32> {
+        let count = self.waypoints.len();
+        if count < 2 || self.segment_duration <= 0. {
+            return self
+                .waypoints
+                .first()
+                .copied()
+                .unwrap_or_else(Point3::origin);
+        }
+
+        let segments = self.segments();
+        let trip = segments as f32 * self.segment_duration;
+        let mut time = time.rem_euclid(self.period());
+        if self.mode == PathMode::PingPong && time > trip {
+            time = trip * 2. - time;
+        }
+        let segment = ((time / self.segment_duration) as usize).min(segments - 1);
+        let t = (time - segment as f32 * self.segment_duration) / self.segment_duration;
+        let t = self.easing.apply(t.min(1.));
+
+        // ping-pong paths repeat their end points so the spline doesn't overshoot them
+        let waypoint = |i: isize| match self.mode {
+            PathMode::Loop => self.waypoints[i.rem_euclid(count as isize) as usize],
+            PathMode::PingPong => self.waypoints[i.max(0).min(count as isize - 1) as usize],
+        };
+        let i = segment as isize;
+        catmull_rom(
+            &waypoint(i - 1),
+            &waypoint(i),
+            &waypoint(i + 1),
+            &waypoint(i + 2),
+            t,
+        )
+    }
+}
+
+// the point `t` of the way between `p1` and `p2`
+fn catmull_rom(
+    p0: &Point3<f32>,
+    p1: &Point3<f32>,
+    p2: &Point3<f32>,
+    p3: &Point3<f32>,
+    t: f32,
+) -> Point3<f32> {
+    let (p0, p1, p2, p3) = (p0.coords, p1.coords, p2.coords, p3.coords);
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let coords: Vector3<f32> = (p1 * 2.
+        + (p2 - p0) * t
+        + (p0 * 2. - p1 * 5. + p2 * 4. - p3) * t2
+        + (p3 - p0 + (p1 - p2) * 3.) * t3)
+        * 0.5;
+    Point3::from(coords)
+}
+
+struct Platform {
+    collider: ColliderHandle,
+    path: Path,
+    time: f32,
+}
+
+/// Kinematic bodies moving along paths.
+#[derive(Default)]
+pub struct Platforms {
+    // in the order they were added
+    platforms: Vec<Platform>,
+}
+
+impl Platforms {
+    /// Moves the kinematic body `collider` is attached to along `path`, starting from its first
+    /// waypoint.
+    pub fn insert(&mut self, collider: ColliderHandle, path: Path) {
+        self.platforms.push(Platform {
+            collider,
+            path,
+            time: 0.,
+        });
+    }
+
+    /// Every platform along with how many seconds it has been moving for.
+    pub fn times(&self) -> Vec<(ColliderHandle, f32)> {
+        self.platforms
+            .iter()
+            .map(|platform| (platform.collider, platform.time))
+            .collect()
+    }
+
+    /// Puts platforms back where they were, as returned by `times`. Platforms missing from
+    /// `times` are left where they are.
+    pub fn restore<I>(&mut self, times: I)
+    where
+        I: IntoIterator<Item = (ColliderHandle, f32)>,
+    {
+        let times = times.into_iter().collect::<HashMap<_, _>>();
+        for platform in self.platforms.iter_mut() {
+            if let Some(time) = times.get(&platform.collider) {
+                platform.time = *time;
+            }
+        }
+    }
+
+    /// Advances every platform by `dt` seconds, to be reached by the end of the next step.
+    pub fn update(&mut self, physics: &mut PhysicsContext, dt: f32) {
+        self.platforms
+            .retain(|platform| physics.collider(platform.collider).is_some());
+
+        for platform in self.platforms.iter_mut() {
+            platform.time += dt;
+            let position = platform.path.position_at(platform.time);
+            if let Some(body) = physics.rigid_body_mut(platform.collider) {
+                body.set_next_kinematic_position(Isometry::translation(
+                    position.x, position.y, position.z,
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1.0e-4;
+
+    fn path(mode: PathMode) -> Path {
+        Path {
+            waypoints: vec![
+                Point3::new(0., 0., 0.),
+                Point3::new(4., 0., 0.),
+                Point3::new(4., 0., 4.),
+                Point3::new(0., 2., 4.),
+            ],
+            segment_duration: 2.,
+            easing: Easing::SmoothStep,
+            mode,
+        }
+    }
+
+    fn assert_near(a: Point3<f32>, b: Point3<f32>) {
+        assert!((a - b).norm() < EPSILON, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn passes_through_every_waypoint() {
+        for mode in [PathMode::Loop, PathMode::PingPong].iter() {
+            let path = path(*mode);
+            for (i, waypoint) in path.waypoints.iter().enumerate() {
+                assert_near(
+                    path.position_at(i as f32 * path.segment_duration),
+                    *waypoint,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ping_pong_turns_around_at_either_end() {
+        let path = path(PathMode::PingPong);
+        assert_eq!(path.period(), 12.);
+        for offset in [0.5, 1.5, 3., 5.].iter() {
+            assert_near(path.position_at(6. + offset), path.position_at(6. - offset));
+        }
+        assert_near(path.position_at(12.), path.waypoints[0]);
+        assert_near(path.position_at(13.), path.position_at(1.));
+    }
+
+    #[test]
+    fn loop_wraps_back_to_the_first_waypoint() {
+        let path = path(PathMode::Loop);
+        assert_eq!(path.period(), 8.);
+        assert_near(path.position_at(8.), path.waypoints[0]);
+        assert_near(path.position_at(9.5), path.position_at(1.5));
+        assert_near(path.position_at(-0.5), path.position_at(7.5));
+
+        // the last segment heads back to the first waypoint rather than stopping at the last
+        let last = path.position_at(7.);
+        let (from, to) = (path.waypoints[3], path.waypoints[0]);
+        assert!((last - from).norm() > 0.5 && (last - to).norm() > 0.5);
+        assert!(last.y > to.y && last.y < from.y);
+    }
+}
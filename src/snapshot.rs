@@ -0,0 +1,162 @@
+use crate::physics::PhysicsSnapshot;
+use rapier3d::geometry::ColliderHandle;
+use rscsg::dim3::{Csg, Triangle, Vector};
+use serde::{Deserialize, Serialize};
+
+/// A copy of the simulation that can be restored later, or written out with `to_bytes` to
+/// share a bug report.
+///
+/// Handles are the same after a restore. See `PhysicsSnapshot` for how one read back from bytes
+/// differs from the original run.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub(crate) physics: PhysicsSnapshot,
+    pub(crate) terrain: Solid,
+    pub(crate) ground: ColliderHandle,
+    pub(crate) capsule: ColliderHandle,
+    pub(crate) debris: Vec<(ColliderHandle, Solid)>,
+    /// Every spawned body with its age in seconds.
+    pub(crate) spawned: Vec<(ColliderHandle, f32)>,
+    /// Every piece of debris with its age in seconds.
+    pub(crate) debris_ages: Vec<(ColliderHandle, f32)>,
+    /// Every moving platform with how long it has been moving for, in seconds.
+    pub(crate) platforms: Vec<(ColliderHandle, f32)>,
+}
+
+#[allow(unused)]
+impl Snapshot {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
+
+/// The triangles of a CSG solid, which can't be serialized itself.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Solid(Vec<([[f32; 3]; 3], [f32; 3])>);
+
+impl Solid {
+    pub fn new(csg: &Csg) -> Self {
+        let triangles = csg
+            .iter_triangles()
+            .map(|triangle| {
+                let Vector(nx, ny, nz) = triangle.normal;
+                let [a, b, c] = triangle.positions;
+                (
+                    [[a.0, a.1, a.2], [b.0, b.1, b.2], [c.0, c.1, c.2]],
+                    [nx, ny, nz],
+                )
+            })
+            .collect();
+        Solid(triangles)
+    }
+
+    pub fn to_csg(&self) -> Csg {
+        let vector = |[x, y, z]: [f32; 3]| Vector(x, y, z);
+        let triangles = self
+            .0
+            .iter()
+            .map(|([a, b, c], normal)| Triangle {
+                positions: [vector(*a), vector(*b), vector(*c)],
+                normal: vector(*normal),
+            })
+            .collect();
+        crate::islands::to_csg(triangles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::PhysicsContext;
+    use crate::trimesh::TriMesh;
+    use rapier3d::dynamics::RigidBodyBuilder;
+    use rapier3d::geometry::ColliderBuilder;
+    use rapier3d::na::Vector3;
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let terrain = Csg::cube(Vector(10., 1., 10.), true);
+        let mut physics = PhysicsContext::new(0., -9.81, 0.);
+        let ground = physics.add_csg(RigidBodyBuilder::new_static().build(), &terrain);
+        let ball = physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .translation(1., 3., -2.)
+                .linvel(0.5, 0., 0.)
+                .angvel(Vector3::new(0., 2., 0.))
+                .build(),
+            ColliderBuilder::ball(0.5).build(),
+        );
+        for _ in 0..10 {
+            physics.step();
+        }
+        let position = *physics.rigid_body(ball).unwrap().position();
+        let velocity = *physics.rigid_body(ball).unwrap().linvel();
+        let angular_velocity = *physics.rigid_body(ball).unwrap().angvel();
+        let mass_properties = *physics.rigid_body(ball).unwrap().mass_properties();
+
+        let snapshot = Snapshot {
+            physics: physics.snapshot(),
+            terrain: Solid::new(&terrain),
+            ground,
+            capsule: ball,
+            debris: vec![],
+            spawned: vec![(ball, 1.5)],
+            debris_ages: vec![],
+            platforms: vec![],
+        };
+        let bytes = snapshot.to_bytes().unwrap();
+        let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+
+        // moved on so the restore has something to undo
+        for _ in 0..10 {
+            physics.step();
+        }
+        physics.restore(&snapshot.physics);
+
+        assert_eq!(snapshot.spawned, vec![(ball, 1.5)]);
+        let body = physics.rigid_body(ball).unwrap();
+        assert_eq!(*body.position(), position);
+        assert_eq!(*body.linvel(), velocity);
+        assert_eq!(*body.angvel(), angular_velocity);
+        assert_eq!(*body.mass_properties(), mass_properties);
+        let ground = physics.collider(snapshot.ground).unwrap();
+        assert!(ground.shape().as_shape::<TriMesh>().is_some());
+        assert_eq!(
+            snapshot.terrain.to_csg().iter_triangles().count(),
+            terrain.iter_triangles().count()
+        );
+    }
+
+    #[test]
+    fn replays_exactly_from_memory() {
+        let terrain = Csg::cube(Vector(10., 1., 10.), true);
+        let mut physics = PhysicsContext::new(0., -9.81, 0.);
+        physics.add_csg(RigidBodyBuilder::new_static().build(), &terrain);
+        let ball = physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .translation(0., 2., 0.)
+                .linvel(1., 0., 0.)
+                .build(),
+            ColliderBuilder::ball(0.5).build(),
+        );
+        // rolling along the ground so there are contacts to carry over
+        for _ in 0..60 {
+            physics.step();
+        }
+        let snapshot = physics.snapshot();
+        for _ in 0..30 {
+            physics.step();
+        }
+        let position = *physics.rigid_body(ball).unwrap().position();
+
+        physics.restore(&snapshot);
+        for _ in 0..30 {
+            physics.step();
+        }
+        assert_eq!(*physics.rigid_body(ball).unwrap().position(), position);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::entity::{EntityKind, DIGGER_FLAG};
+use rapier3d::dynamics::{RigidBody, RigidBodyBuilder};
+use rapier3d::geometry::{Collider, ColliderBuilder};
+use rapier3d::na::{Point3, Vector3};
+use std::collections::HashMap;
+
+/// How many points are thrown into a convex hull spawn.
+const CONVEX_POINTS: usize = 12;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SpawnShape {
+    Ball,
+    Cuboid,
+    Capsule,
+    Convex,
+}
+
+impl SpawnShape {
+    /// The shape after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            SpawnShape::Ball => SpawnShape::Cuboid,
+            SpawnShape::Cuboid => SpawnShape::Capsule,
+            SpawnShape::Capsule => SpawnShape::Convex,
+            SpawnShape::Convex => SpawnShape::Ball,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpawnerConfig {
+    /// The center of the spawn area.
+    pub position: Point3<f32>,
+    /// Half extents of the box around `position` bodies are spawned in.
+    pub area: Vector3<f32>,
+    pub shape: SpawnShape,
+    /// Smallest and largest size of a spawned body. A radius for balls, capsules and convex
+    /// shapes, a half extent for cuboids.
+    pub size: (f32, f32),
+    /// Linear velocity given to every spawned body.
+    pub velocity: Vector3<f32>,
+    /// Seconds between two bursts.
+    pub interval: f32,
+    /// How many bodies are spawned at once.
+    pub burst: u32,
+    /// Whether spawned balls carve the terrain where they hit it.
+    pub digger: bool,
+}
+
+impl Default for SpawnerConfig {
+    fn default() -> Self {
+        Self {
+            position: Point3::new(0., 10., 0.),
+            area: Vector3::zeros(),
+            shape: SpawnShape::Ball,
+            size: (1., 1.),
+            velocity: Vector3::zeros(),
+            interval: 1.5,
+            burst: 1,
+            digger: false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SpawnerId(u32);
+
+/// Every spawner's configuration, looked up by the cron jobs that run them.
+#[derive(Default)]
+pub struct Spawners {
+    next_id: u32,
+    configs: HashMap<SpawnerId, SpawnerConfig>,
+    rng: Rng,
+}
+
+impl Spawners {
+    pub fn insert(&mut self, config: SpawnerConfig) -> SpawnerId {
+        let id = SpawnerId(self.next_id);
+        self.next_id += 1;
+        self.configs.insert(id, config);
+        id
+    }
+
+    pub fn remove(&mut self, id: SpawnerId) -> Option<SpawnerConfig> {
+        self.configs.remove(&id)
+    }
+
+    #[allow(unused)]
+    pub fn get(&self, id: SpawnerId) -> Option<&SpawnerConfig> {
+        self.configs.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: SpawnerId) -> Option<&mut SpawnerConfig> {
+        self.configs.get_mut(&id)
+    }
+
+    /// Builds one burst worth of bodies for the spawner `id`.
+    pub fn burst(&mut self, id: SpawnerId) -> Vec<(RigidBody, Collider)> {
+        let config = match self.configs.get(&id) {
+            Some(config) => config,
+            None => return vec![],
+        };
+        let rng = &mut self.rng;
+        (0..config.burst)
+            .map(|_| {
+                let offset = Vector3::new(
+                    rng.range(-config.area.x, config.area.x),
+                    rng.range(-config.area.y, config.area.y),
+                    rng.range(-config.area.z, config.area.z),
+                );
+                let position = config.position + offset;
+                let body = RigidBodyBuilder::new_dynamic()
+                    .translation(position.x, position.y, position.z)
+                    .linvel(config.velocity.x, config.velocity.y, config.velocity.z)
+                    .build();
+                (body, build_collider(config, rng))
+            })
+            .collect()
+    }
+}
+
+fn build_collider(config: &SpawnerConfig, rng: &mut Rng) -> Collider {
+    let (min, max) = config.size;
+    let builder = match config.shape {
+        SpawnShape::Ball => ColliderBuilder::ball(rng.range(min, max)),
+        SpawnShape::Cuboid => ColliderBuilder::cuboid(
+            rng.range(min, max),
+            rng.range(min, max),
+            rng.range(min, max),
+        ),
+        SpawnShape::Capsule => {
+            let radius = rng.range(min, max);
+            ColliderBuilder::capsule_y(radius, radius)
+        }
+        SpawnShape::Convex => {
+            let radius = rng.range(min, max);
+            let points = (0..CONVEX_POINTS)
+                .map(|_| Point3::from(rng.unit_vector() * radius))
+                .collect::<Vec<_>>();
+            // degenerate point clouds don't have a hull
+            ColliderBuilder::convex_hull(&points).unwrap_or_else(|| ColliderBuilder::ball(radius))
+        }
+    };
+    let kind = match config.shape {
+        SpawnShape::Ball => EntityKind::Ball,
+        _ => EntityKind::Prop,
+    };
+    let mut user_data = kind.user_data();
+    if config.digger && kind == EntityKind::Ball {
+        user_data |= DIGGER_FLAG;
+    }
+    builder.user_data(user_data).build()
+}
+
+/// A xorshift generator, plenty for scattering spawns around.
+struct Rng(u64);
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// A float in `[0, 1)`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    fn unit_vector(&mut self) -> Vector3<f32> {
+        loop {
+            let v = Vector3::new(
+                self.range(-1., 1.),
+                self.range(-1., 1.),
+                self.range(-1., 1.),
+            );
+            let norm = v.norm();
+            if norm > 0.01 && norm <= 1. {
+                return v / norm;
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::layers::{Layer, LayerMask};
+use crate::physics::PhysicsContext;
+use rapier3d::geometry::Ball;
+use rapier3d::math::Isometry;
+use rapier3d::na::{Point3, Vector3};
+
+/// Seconds between two points of a predicted arc.
+const ARC_STEP: f32 = 1. / 30.;
+/// How many points of an arc are predicted at most.
+const ARC_SAMPLES: usize = 90;
+
+pub struct ThrowConfig {
+    /// Launch speed of a throw released straight away.
+    pub min_speed: f32,
+    /// Launch speed once fully charged.
+    pub max_speed: f32,
+    /// Seconds it takes to fully charge a throw.
+    pub charge_time: f32,
+    /// How far above the camera's forward vector throws go, in radians. The camera looks down
+    /// at the player so throwing straight along it would hit the ground.
+    pub aim_pitch: f32,
+    /// Radius of the thrown balls.
+    pub radius: f32,
+}
+
+impl Default for ThrowConfig {
+    fn default() -> Self {
+        Self {
+            min_speed: 4.,
+            max_speed: 20.,
+            charge_time: 1.,
+            aim_pitch: std::f32::consts::FRAC_PI_4 + 0.3,
+            radius: 0.3,
+        }
+    }
+}
+
+/// Charges up throws while the throw button is held.
+pub struct Thrower {
+    pub config: ThrowConfig,
+    // seconds spent charging, `None` when not charging at all
+    charge: Option<f32>,
+}
+
+impl Thrower {
+    pub fn new(config: ThrowConfig) -> Self {
+        Self {
+            config,
+            charge: None,
+        }
+    }
+
+    /// Starts charging a throw, unless one is already being charged.
+    pub fn start_charging(&mut self) {
+        if self.charge.is_none() {
+            self.charge = Some(0.);
+        }
+    }
+
+    /// Charges the current throw for another `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        if let Some(charge) = self.charge.as_mut() {
+            *charge = (*charge + dt).min(self.config.charge_time);
+        }
+    }
+
+    /// The speed the current throw would be launched at, `None` if not charging.
+    pub fn speed(&self) -> Option<f32> {
+        let charge = self.charge?;
+        let t = if self.config.charge_time > 0. {
+            charge / self.config.charge_time
+        } else {
+            1.
+        };
+        Some(self.config.min_speed + (self.config.max_speed - self.config.min_speed) * t)
+    }
+
+    /// Stops charging and returns the speed to launch at, `None` if not charging.
+    pub fn release(&mut self) -> Option<f32> {
+        let speed = self.speed();
+        self.charge = None;
+        speed
+    }
+
+    /// The velocity of a throw at `speed` towards `forward`, pitched up by `aim_pitch`.
+    pub fn launch_velocity(&self, forward: &Vector3<f32>, speed: f32) -> Vector3<f32> {
+        let horizontal = Vector3::new(forward.x, 0., forward.z);
+        let horizontal = if horizontal.norm_squared() > f32::EPSILON {
+            horizontal.normalize()
+        } else {
+            -Vector3::z()
+        };
+        let pitch = forward.y.clamp(-1., 1.).asin() + self.config.aim_pitch;
+        (horizontal * pitch.cos() + Vector3::y() * pitch.sin()) * speed
+    }
+}
+
+/// The path of the center of a ball of `radius` launched from `origin` at `velocity`, following
+/// gravity zones and stopping at the first thing it would hit. Drag and fluids are ignored.
+pub fn predict_arc(
+    physics: &PhysicsContext,
+    origin: &Point3<f32>,
+    velocity: &Vector3<f32>,
+    radius: f32,
+) -> Vec<Point3<f32>> {
+    let filter = LayerMask::ALL
+        .without(Layer::Trigger)
+        .without(Layer::Player)
+        .into();
+    let ball = Ball::new(radius);
+    let mut position = *origin;
+    let mut velocity = *velocity;
+    let mut points = vec![position];
+    for _ in 0..ARC_SAMPLES {
+        velocity += physics.gravity_at(&position) * ARC_STEP;
+        let delta = velocity * ARC_STEP;
+        let distance = delta.norm();
+        if distance <= f32::EPSILON {
+            break;
+        }
+        let direction = delta / distance;
+        let start = Isometry::translation(position.x, position.y, position.z);
+        if let Some((_collider, toi)) =
+            physics.cast_shape(&start, &direction, &ball, distance, filter)
+        {
+            points.push(position + direction * toi.toi);
+            break;
+        }
+        position += delta;
+        points.push(position);
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1.0e-5;
+
+    fn thrower(charge_time: f32, aim_pitch: f32) -> Thrower {
+        Thrower::new(ThrowConfig {
+            min_speed: 4.,
+            max_speed: 20.,
+            charge_time,
+            aim_pitch,
+            radius: 0.3,
+        })
+    }
+
+    #[test]
+    fn speed_ramps_up_while_charging() {
+        let mut thrower = thrower(1., 0.);
+        assert_eq!(thrower.speed(), None);
+
+        thrower.start_charging();
+        assert_eq!(thrower.speed(), Some(4.));
+        thrower.update(0.5);
+        assert_eq!(thrower.speed(), Some(12.));
+        thrower.update(5.);
+        assert_eq!(thrower.speed(), Some(20.));
+
+        assert_eq!(thrower.release(), Some(20.));
+        assert_eq!(thrower.speed(), None);
+        assert_eq!(thrower.release(), None);
+    }
+
+    #[test]
+    fn restarting_a_charge_keeps_it() {
+        let mut thrower = thrower(1., 0.);
+        thrower.start_charging();
+        thrower.update(0.25);
+        thrower.start_charging();
+        assert_eq!(thrower.speed(), Some(8.));
+    }
+
+    #[test]
+    fn instant_charge_is_full_speed() {
+        let mut thrower = thrower(0., 0.);
+        thrower.start_charging();
+        assert_eq!(thrower.speed(), Some(20.));
+        thrower.update(1.);
+        assert_eq!(thrower.speed(), Some(20.));
+    }
+
+    #[test]
+    fn launch_velocity_follows_forward() {
+        let thrower = thrower(1., 0.);
+        let velocity = thrower.launch_velocity(&Vector3::x(), 10.);
+        assert!((velocity - Vector3::new(10., 0., 0.)).norm() < EPSILON);
+    }
+
+    #[test]
+    fn launch_velocity_is_pitched_up() {
+        let pitch = std::f32::consts::FRAC_PI_4;
+        let thrower = thrower(1., pitch);
+        let forward = Vector3::new(0., -pitch.sin(), -pitch.cos());
+        // looking down at 45 degrees and pitching up by as much throws level
+        let velocity = thrower.launch_velocity(&forward, 10.);
+        assert!((velocity - Vector3::new(0., 0., -10.)).norm() < EPSILON);
+
+        let velocity = thrower.launch_velocity(&-Vector3::z(), 10.);
+        assert!((velocity.norm() - 10.).abs() < EPSILON);
+        assert!((velocity.y - 10. * pitch.sin()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn launch_velocity_straight_down_faces_forward() {
+        let thrower = thrower(1., 0.);
+        let velocity = thrower.launch_velocity(&-Vector3::y(), 10.);
+        assert!((velocity - Vector3::new(0., -10., 0.)).norm() < EPSILON);
+    }
+
+    #[test]
+    fn arc_stops_a_radius_short_of_the_floor() {
+        use rapier3d::dynamics::RigidBodyBuilder;
+        use rapier3d::geometry::ColliderBuilder;
+
+        let mut physics = PhysicsContext::new(0., -10., 0.);
+        physics.add_body(
+            RigidBodyBuilder::new_static().build(),
+            ColliderBuilder::cuboid(10., 0.5, 10.).build(),
+        );
+        physics.step();
+
+        let arc = predict_arc(
+            &physics,
+            &Point3::new(0., 3., 0.),
+            &Vector3::new(1., 0., 0.),
+            0.3,
+        );
+        let last = arc.last().unwrap();
+        // the top of the floor is at 0.5, and shape casts stop a little short of contact
+        assert!((last.y - 0.8).abs() < 1.0e-2);
+    }
+}
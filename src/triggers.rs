@@ -0,0 +1,28 @@
+use crate::entity::EntityKind;
+use rapier3d::geometry::ColliderHandle;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriggerKind {
+    Goal,
+    Checkpoint,
+    Hazard,
+}
+
+/// A sensor volume along with the kinds of entities it reacts to.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Trigger {
+    pub kind: TriggerKind,
+    pub watches: Vec<EntityKind>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TriggerEvent {
+    pub kind: TriggerKind,
+    pub trigger: ColliderHandle,
+    /// The collider that entered or left the trigger.
+    pub other: ColliderHandle,
+    pub other_kind: EntityKind,
+    /// Whether `other` entered the trigger or left it.
+    pub entered: bool,
+}
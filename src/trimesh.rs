@@ -5,32 +5,198 @@ use rapier3d::geometry::{
     FeatureId, PointProjection, Ray, RayIntersection, Shape, ShapeType, SimdCompositeShape,
     Triangle, TrianglePointLocation, TypedShape, TypedSimdCompositeShape, AABB,
 };
-use rapier3d::math::{Isometry, Point, Real, Rotation};
-use rapier3d::na::RealField;
+use rapier3d::math::{Isometry, Point, Real, Rotation, Vector};
+use rapier3d::na::{Matrix3, RealField, Unit};
 use rapier3d::parry::bounding_volume::BoundingSphere;
 use rapier3d::parry::partitioning::SimdQuadTree;
 use rapier3d::parry::query::details::{
-    PointCompositeShapeProjWithFeatureBestFirstVisitor,
+    closest_points_composite_shape_shape, closest_points_shape_composite_shape,
+    contact_composite_shape_shape, contact_shape_composite_shape, distance_composite_shape_shape,
+    distance_shape_composite_shape, intersection_test_composite_shape_shape,
+    intersection_test_shape_composite_shape, nonlinear_time_of_impact_composite_shape_shape,
+    nonlinear_time_of_impact_shape_composite_shape, time_of_impact_composite_shape_shape,
+    time_of_impact_shape_composite_shape, PointCompositeShapeProjWithFeatureBestFirstVisitor,
     PointCompositeShapeProjWithLocationBestFirstVisitor,
     RayCompositeShapeToiAndNormalBestFirstVisitor, RayCompositeShapeToiBestFirstVisitor,
 };
 use rapier3d::parry::query::visitors::CompositePointContainmentTest;
-use rapier3d::parry::query::{PointQuery, PointQueryWithLocation, RayCast};
+use rapier3d::parry::query::{
+    ClosestPoints, Contact, ContactManifold, ContactManifoldsWorkspace, DefaultQueryDispatcher,
+    NonlinearRigidMotion, PersistentQueryDispatcher, PointQuery, PointQueryWithLocation,
+    QueryDispatcher, RayCast, Unsupported, TOI,
+};
+use std::collections::HashMap;
 use std::iter::Map;
 use std::slice::ChunksExact;
 
+/// The id reported through `TypedShape::Custom` by [`TriMesh`].
+pub const TRIMESH_SHAPE_ID: u32 = 69;
+
+/// Vertices closer than this along every axis are considered to be the same vertex.
+const WELD_EPSILON: Real = 1.0e-4;
+/// Contact normals this close to the face normal are never corrected.
+const NORMAL_EPSILON: Real = 1.0e-4;
+
+/// Merges the vertices sharing the same position.
+///
+/// Returns the welded index of every input vertex along with the number of distinct vertices.
+pub fn weld_vertices(vertices: &[Point<Real>]) -> (Vec<u32>, usize) {
+    let mut welded = HashMap::new();
+    let ids = vertices
+        .iter()
+        .map(|vertex| {
+            let key = [
+                (vertex.x / WELD_EPSILON).round() as i64,
+                (vertex.y / WELD_EPSILON).round() as i64,
+                (vertex.z / WELD_EPSILON).round() as i64,
+            ];
+            let next = welded.len() as u32;
+            *welded.entry(key).or_insert(next)
+        })
+        .collect();
+    (ids, welded.len())
+}
+
+/// The connectivity of a [`TriMesh`] once vertices sharing a position are welded together.
+///
+/// Edge `i` of a triangle goes from its vertex `i` to its vertex `(i + 1) % 3`, matching the
+/// edge ids of `TrianglePointLocation::OnEdge`.
+#[derive(Clone)]
+pub struct TriMeshTopology {
+    vertex_ids: Vec<u32>,
+    adjacency: Vec<[Option<u32>; 3]>,
+    face_normals: Vec<Vector<Real>>,
+    edge_normals: Vec<[Vector<Real>; 3]>,
+    vertex_normals: Vec<Vector<Real>>,
+}
+
+impl TriMeshTopology {
+    fn new(vertices: &[Point<Real>]) -> Self {
+        let (vertex_ids, num_vertices) = weld_vertices(vertices);
+        let num_triangles = vertices.len() / 3;
+
+        let face_normals = vertices
+            .chunks_exact(3)
+            .map(|v| {
+                Triangle::new(v[0], v[1], v[2])
+                    .normal()
+                    .map_or(Vector::zeros(), Unit::into_inner)
+            })
+            .collect::<Vec<_>>();
+
+        // Non-manifold edges (shared by more than two triangles) only link the first pair.
+        let mut adjacency = vec![[None; 3]; num_triangles];
+        let mut open_edges = HashMap::<_, (usize, usize)>::new();
+        for triangle in 0..num_triangles {
+            for edge in 0..3 {
+                let a = vertex_ids[triangle * 3 + edge];
+                let b = vertex_ids[triangle * 3 + (edge + 1) % 3];
+                if a == b {
+                    continue;
+                }
+                let key = (a.min(b), a.max(b));
+                match open_edges.remove(&key) {
+                    Some((other, other_edge)) => {
+                        adjacency[triangle][edge] = Some(other as u32);
+                        adjacency[other][other_edge] = Some(triangle as u32);
+                    }
+                    None => {
+                        open_edges.insert(key, (triangle, edge));
+                    }
+                }
+            }
+        }
+
+        let edge_normals = (0..num_triangles)
+            .map(|triangle| {
+                let mut normals = [face_normals[triangle]; 3];
+                for (edge, normal) in normals.iter_mut().enumerate() {
+                    if let Some(other) = adjacency[triangle][edge] {
+                        *normal = (*normal + face_normals[other as usize])
+                            .try_normalize(Real::EPSILON)
+                            .unwrap_or(*normal);
+                    }
+                }
+                normals
+            })
+            .collect();
+
+        // Angle-weighted pseudo-normals.
+        let mut vertex_normals = vec![Vector::zeros(); num_vertices];
+        for (triangle, v) in vertices.chunks_exact(3).enumerate() {
+            for corner in 0..3 {
+                let e1 = v[(corner + 1) % 3] - v[corner];
+                let e2 = v[(corner + 2) % 3] - v[corner];
+                let angle = e1.angle(&e2);
+                if angle.is_finite() {
+                    let id = vertex_ids[triangle * 3 + corner] as usize;
+                    vertex_normals[id] += face_normals[triangle] * angle;
+                }
+            }
+        }
+        for normal in vertex_normals.iter_mut() {
+            *normal = normal
+                .try_normalize(Real::EPSILON)
+                .unwrap_or(Vector::zeros());
+        }
+
+        Self {
+            vertex_ids,
+            adjacency,
+            face_normals,
+            edge_normals,
+            vertex_normals,
+        }
+    }
+
+    /// The welded id of the `corner`-th vertex of a triangle.
+    pub fn vertex_id(&self, triangle: u32, corner: u32) -> u32 {
+        self.vertex_ids[triangle as usize * 3 + corner as usize]
+    }
+
+    /// The triangle sharing the given edge, if any.
+    pub fn adjacent_triangle(&self, triangle: u32, edge: u32) -> Option<u32> {
+        self.adjacency[triangle as usize][edge as usize]
+    }
+
+    /// The normal of a triangle or zero if it is degenerate.
+    pub fn face_normal(&self, triangle: u32) -> Vector<Real> {
+        self.face_normals[triangle as usize]
+    }
+
+    /// The pseudo-normal of a triangle edge: the average of both adjacent face normals.
+    pub fn edge_pseudo_normal(&self, triangle: u32, edge: u32) -> Vector<Real> {
+        self.edge_normals[triangle as usize][edge as usize]
+    }
+
+    /// The angle-weighted pseudo-normal of the `corner`-th vertex of a triangle.
+    pub fn vertex_pseudo_normal(&self, triangle: u32, corner: u32) -> Vector<Real> {
+        self.vertex_normals[self.vertex_id(triangle, corner) as usize]
+    }
+
+    /// Does every edge of the mesh have a triangle on both sides?
+    pub fn is_closed(&self) -> bool {
+        self.adjacency
+            .iter()
+            .all(|edges| edges.iter().all(Option::is_some))
+    }
+}
+
 #[derive(Clone)]
 /// A triangle mesh.
 pub struct TriMesh {
     quadtree: SimdQuadTree<u32>,
     vertices: Vec<Point<Real>>,
+    topology: TriMeshTopology,
 }
 
 impl TriMesh {
-    /// Creates a new triangle mesh from a vertex buffer and an index buffer.
+    /// Creates a new triangle mesh from a non-indexed vertex buffer.
+    ///
+    /// Every three consecutive vertices form one triangle.
     pub fn new(vertices: Vec<Point<Real>>) -> Self {
         debug_assert!(
-            vertices.len() > 3,
+            vertices.len() >= 3,
             "A triangle mesh must contain at least one triangle."
         );
 
@@ -44,7 +210,13 @@ impl TriMesh {
         // update this tree dynamically.
         quadtree.clear_and_rebuild(data, 0.0);
 
-        Self { quadtree, vertices }
+        let topology = TriMeshTopology::new(&vertices);
+
+        Self {
+            quadtree,
+            vertices,
+            topology,
+        }
     }
 
     /// Compute the axis-aligned bounding box of this triangle mesh.
@@ -62,6 +234,11 @@ impl TriMesh {
         &self.quadtree
     }
 
+    /// The connectivity of this mesh.
+    pub fn topology(&self) -> &TriMeshTopology {
+        &self.topology
+    }
+
     /// The number of triangles forming this mesh.
     pub fn num_triangles(&self) -> usize {
         self.vertices.len() / 3
@@ -98,6 +275,191 @@ impl TriMesh {
         &self.vertices[..]
     }
 
+    /// The volume, center of mass and covariance matrix (relative to the center of mass) of the
+    /// solid bounded by this mesh.
+    ///
+    /// Integrates the signed tetrahedra formed by every triangle and the origin so this is only
+    /// meaningful for closed meshes, `None` is returned for open or flat ones.
+    pub fn volume_properties(&self) -> Option<(Real, Point<Real>, Matrix3<Real>)> {
+        if !self.topology.is_closed() {
+            return None;
+        }
+
+        // Covariance of the canonical tetrahedron (0, x, y, z).
+        let canonical = Matrix3::new(2., 1., 1., 1., 2., 1., 1., 1., 2.) / 120.;
+
+        let mut volume = 0.0;
+        let mut com = Vector::zeros();
+        let mut covariance = Matrix3::zeros();
+        for triangle in self.triangles() {
+            let (a, b, c) = (triangle.a.coords, triangle.b.coords, triangle.c.coords);
+            let det = a.dot(&b.cross(&c));
+            let basis = Matrix3::from_columns(&[a, b, c]);
+
+            volume += det / 6.;
+            com += (a + b + c) * det;
+            covariance += basis * canonical * basis.transpose() * det;
+        }
+
+        if volume.abs() <= Real::EPSILON {
+            return None;
+        }
+        // Each tetrahedron's centroid is (a + b + c) / 4 and weighs det / 6.
+        let com = Point::from(com / (24. * volume));
+        // Inside out meshes integrate to the exact opposite.
+        let (volume, covariance) = if volume < 0. {
+            (-volume, -covariance)
+        } else {
+            (volume, covariance)
+        };
+        let covariance = covariance - com.coords * com.coords.transpose() * volume;
+
+        Some((volume, com, covariance))
+    }
+
+    /// Whether `point` is inside the volume enclosed by this mesh.
+    ///
+    /// Uses the pseudo normal of the closest feature so it's only meaningful for closed meshes,
+    /// open ones never contain anything.
+    pub fn contains_point(&self, point: &Point<Real>) -> bool {
+        if !self.topology.is_closed() {
+            return false;
+        }
+        let (projection, (triangle, location)) =
+            self.project_local_point_and_get_location(point, false);
+        let pseudo_normal = match location {
+            TrianglePointLocation::OnEdge(edge, _) => {
+                self.topology.edge_pseudo_normal(triangle, edge)
+            }
+            TrianglePointLocation::OnVertex(corner) => {
+                self.topology.vertex_pseudo_normal(triangle, corner)
+            }
+            TrianglePointLocation::OnFace(..) | TrianglePointLocation::OnSolid => {
+                self.topology.face_normal(triangle)
+            }
+        };
+        (point - projection.point).dot(&pseudo_normal) < 0.0
+    }
+
+    /// Fixes up a contact normal generated against one of the triangles so that it does not
+    /// point into the neighbouring triangles.
+    ///
+    /// Shapes sliding across an internal edge would otherwise get snagged on it (the so called
+    /// ghost collisions) because each triangle is unaware of its neighbours.
+    pub fn correct_contact_normal(
+        &self,
+        triangle: u32,
+        point: &Point<Real>,
+        normal: &Vector<Real>,
+    ) -> Vector<Real> {
+        let face_normal = self.topology.face_normal(triangle);
+        if face_normal == Vector::zeros() || normal.dot(&face_normal) >= 1.0 - NORMAL_EPSILON {
+            return *normal;
+        }
+
+        let tri = self.triangle(triangle);
+        let (_, location) = tri.project_local_point_and_get_location(point, false);
+        match location {
+            TrianglePointLocation::OnEdge(edge, _) => {
+                let other = match self.topology.adjacent_triangle(triangle, edge) {
+                    Some(other) => other,
+                    // Contacts on the boundary of the mesh are legit in every direction.
+                    None => return *normal,
+                };
+                let other_normal = self.topology.face_normal(other);
+
+                // The edge is convex if the neighbour bends away from this triangle's plane.
+                let other_tri = self.triangle(other);
+                let opposite = [other_tri.a, other_tri.b, other_tri.c]
+                    .iter()
+                    .map(|vertex| (vertex - tri.a).dot(&face_normal))
+                    .fold(0.0, Real::min);
+                if opposite > -WELD_EPSILON {
+                    // Flat or concave edges have nothing but the face normal to offer.
+                    return face_normal;
+                }
+
+                // Keep normals inside the cone spanned by both faces, snap the rest onto the
+                // nearest one.
+                let spread = face_normal.dot(&other_normal);
+                if normal.dot(&face_normal) >= spread && normal.dot(&other_normal) >= spread {
+                    *normal
+                } else if normal.dot(&face_normal) >= normal.dot(&other_normal) {
+                    face_normal
+                } else {
+                    other_normal
+                }
+            }
+            TrianglePointLocation::OnVertex(corner) => {
+                let pseudo_normal = self.topology.vertex_pseudo_normal(triangle, corner);
+                if normal.dot(&pseudo_normal) >= face_normal.dot(&pseudo_normal) {
+                    *normal
+                } else {
+                    face_normal
+                }
+            }
+            TrianglePointLocation::OnFace(..) | TrianglePointLocation::OnSolid => face_normal,
+        }
+    }
+
+    /// Applies [`TriMesh::correct_contact_normal`] to manifolds generated against this mesh.
+    ///
+    /// `pos_other` is the position of the other shape relative to this mesh and `flipped` is
+    /// true if this mesh is the second shape of the manifolds.
+    pub fn correct_contact_manifolds<ManifoldData, ContactData>(
+        &self,
+        pos_other: &Isometry<Real>,
+        manifolds: &mut [ContactManifold<ManifoldData, ContactData>],
+        flipped: bool,
+    ) where
+        ContactData: Copy,
+    {
+        for manifold in manifolds.iter_mut() {
+            let (triangle, normal, subshape_pos) = if flipped {
+                (
+                    manifold.subshape2,
+                    manifold.local_n2,
+                    manifold.subshape_pos1,
+                )
+            } else {
+                (
+                    manifold.subshape1,
+                    manifold.local_n1,
+                    manifold.subshape_pos2,
+                )
+            };
+            let point = match manifold.points.first() {
+                Some(contact) if flipped => contact.local_p2,
+                Some(contact) => contact.local_p1,
+                None => continue,
+            };
+
+            let corrected = self.correct_contact_normal(triangle, &point, &normal);
+            if corrected == normal {
+                continue;
+            }
+
+            let other_to_mesh = subshape_pos.map_or(*pos_other, |pos| pos_other * pos);
+            let other_normal = other_to_mesh.inverse_transform_vector(&-corrected);
+            for contact in manifold.points.iter_mut() {
+                let (mesh_point, other_point) = if flipped {
+                    (contact.local_p2, contact.local_p1)
+                } else {
+                    (contact.local_p1, contact.local_p2)
+                };
+                contact.dist = (other_to_mesh * other_point - mesh_point).dot(&corrected);
+            }
+
+            if flipped {
+                manifold.local_n1 = other_normal;
+                manifold.local_n2 = corrected;
+            } else {
+                manifold.local_n1 = corrected;
+                manifold.local_n2 = other_normal;
+            }
+        }
+    }
+
     #[inline]
     fn bounding_sphere(&self, pos: &Isometry<Real>) -> BoundingSphere {
         self.local_aabb().bounding_sphere().transform_by(pos)
@@ -172,7 +534,7 @@ impl RayCast for TriMesh {
                 // We hit a backface.
                 // NOTE: we need this for `TriMesh::is_backface` to work properly.
                 if res.feature == FeatureId::Face(1) {
-                    res.feature = FeatureId::Face(best + self.vertices().len() as u32)
+                    res.feature = FeatureId::Face(best + self.num_triangles() as u32)
                 } else {
                     res.feature = FeatureId::Face(best);
                 }
@@ -194,12 +556,23 @@ impl PointQuery for TriMesh {
     ) -> (PointProjection, FeatureId) {
         let mut visitor =
             PointCompositeShapeProjWithFeatureBestFirstVisitor::new(self, point, false);
-        let (proj, (id, _feature)) = self.quadtree().traverse_best_first(&mut visitor).unwrap().1;
-        let feature_id = FeatureId::Face(id);
-        (proj, feature_id)
+        match self.quadtree().traverse_best_first(&mut visitor) {
+            Some((_, (proj, (id, _feature)))) => (proj, FeatureId::Face(id)),
+            None => (PointProjection::new(false, *point), FeatureId::Unknown),
+        }
     }
 
-    // FIXME: implement distance_to_point too?
+    #[inline]
+    fn distance_to_local_point(&self, point: &Point<Real>, solid: bool) -> Real {
+        let proj = self.project_local_point(point, solid);
+        let distance = rapier3d::na::distance(point, &proj.point);
+
+        if solid || !proj.is_inside {
+            distance
+        } else {
+            -distance
+        }
+    }
 
     #[inline]
     fn contains_local_point(&self, point: &Point<Real>) -> bool {
@@ -220,7 +593,16 @@ impl PointQueryWithLocation for TriMesh {
     ) -> (PointProjection, Self::Location) {
         let mut visitor =
             PointCompositeShapeProjWithLocationBestFirstVisitor::new(self, point, solid);
-        self.quadtree().traverse_best_first(&mut visitor).unwrap().1
+        // The traversal only comes back empty if there are no triangles to project on.
+        self.quadtree()
+            .traverse_best_first(&mut visitor)
+            .map(|(_, result)| result)
+            .unwrap_or_else(|| {
+                (
+                    PointProjection::new(false, *point),
+                    (0, TrianglePointLocation::OnSolid),
+                )
+            })
     }
 }
 
@@ -241,21 +623,29 @@ impl Shape for TriMesh {
         self.aabb(position)
     }
 
-    fn mass_properties(&self, _density: Real) -> MassProperties {
-        MassProperties {
-            inv_mass: 0.0,
-            inv_principal_inertia_sqrt: rapier3d::na::zero(),
-            principal_inertia_local_frame: Rotation::identity(),
-            local_com: Point::origin(),
+    fn mass_properties(&self, density: Real) -> MassProperties {
+        match self.volume_properties() {
+            Some((volume, local_com, covariance)) => {
+                let covariance = covariance * density;
+                let inertia = Matrix3::identity() * covariance.trace() - covariance;
+                MassProperties::with_inertia_matrix(local_com, volume * density, inertia)
+            }
+            // Without a volume this can only ever behave like a static mesh.
+            None => MassProperties {
+                inv_mass: 0.0,
+                inv_principal_inertia_sqrt: rapier3d::na::zero(),
+                principal_inertia_local_frame: Rotation::identity(),
+                local_com: Point::origin(),
+            },
         }
     }
 
     fn shape_type(&self) -> ShapeType {
-        ShapeType::TriMesh
+        ShapeType::Custom
     }
 
     fn as_typed_shape(&self) -> TypedShape {
-        TypedShape::Custom(69)
+        TypedShape::Custom(TRIMESH_SHAPE_ID)
     }
 
     fn ccd_thickness(&self) -> Real {
@@ -273,3 +663,484 @@ impl Shape for TriMesh {
         Some(self as &dyn SimdCompositeShape)
     }
 }
+
+/// Handles every query involving a [`TriMesh`] so it can be used as a collider.
+///
+/// Meant to be chained in front of the `DefaultQueryDispatcher` which handles everything else
+/// (including the individual triangles of the mesh).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TriMeshDispatcher;
+
+impl QueryDispatcher for TriMeshDispatcher {
+    fn intersection_test(
+        &self,
+        pos12: &Isometry<Real>,
+        g1: &dyn Shape,
+        g2: &dyn Shape,
+    ) -> Result<bool, Unsupported> {
+        if let Some(trimesh1) = g1.as_shape::<TriMesh>() {
+            Ok(intersection_test_composite_shape_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                trimesh1,
+                g2,
+            ))
+        } else if let Some(trimesh2) = g2.as_shape::<TriMesh>() {
+            Ok(intersection_test_shape_composite_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                g1,
+                trimesh2,
+            ))
+        } else {
+            Err(Unsupported)
+        }
+    }
+
+    fn distance(
+        &self,
+        pos12: &Isometry<Real>,
+        g1: &dyn Shape,
+        g2: &dyn Shape,
+    ) -> Result<Real, Unsupported> {
+        if let Some(trimesh1) = g1.as_shape::<TriMesh>() {
+            Ok(distance_composite_shape_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                trimesh1,
+                g2,
+            ))
+        } else if let Some(trimesh2) = g2.as_shape::<TriMesh>() {
+            Ok(distance_shape_composite_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                g1,
+                trimesh2,
+            ))
+        } else {
+            Err(Unsupported)
+        }
+    }
+
+    fn contact(
+        &self,
+        pos12: &Isometry<Real>,
+        g1: &dyn Shape,
+        g2: &dyn Shape,
+        prediction: Real,
+    ) -> Result<Option<Contact>, Unsupported> {
+        if let Some(trimesh1) = g1.as_shape::<TriMesh>() {
+            Ok(contact_composite_shape_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                trimesh1,
+                g2,
+                prediction,
+            ))
+        } else if let Some(trimesh2) = g2.as_shape::<TriMesh>() {
+            Ok(contact_shape_composite_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                g1,
+                trimesh2,
+                prediction,
+            ))
+        } else {
+            Err(Unsupported)
+        }
+    }
+
+    fn closest_points(
+        &self,
+        pos12: &Isometry<Real>,
+        g1: &dyn Shape,
+        g2: &dyn Shape,
+        max_dist: Real,
+    ) -> Result<ClosestPoints, Unsupported> {
+        if let Some(trimesh1) = g1.as_shape::<TriMesh>() {
+            Ok(closest_points_composite_shape_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                trimesh1,
+                g2,
+                max_dist,
+            ))
+        } else if let Some(trimesh2) = g2.as_shape::<TriMesh>() {
+            Ok(closest_points_shape_composite_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                g1,
+                trimesh2,
+                max_dist,
+            ))
+        } else {
+            Err(Unsupported)
+        }
+    }
+
+    fn time_of_impact(
+        &self,
+        pos12: &Isometry<Real>,
+        vel12: &Vector<Real>,
+        g1: &dyn Shape,
+        g2: &dyn Shape,
+        max_toi: Real,
+    ) -> Result<Option<TOI>, Unsupported> {
+        if let Some(trimesh1) = g1.as_shape::<TriMesh>() {
+            Ok(time_of_impact_composite_shape_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                vel12,
+                trimesh1,
+                g2,
+                max_toi,
+            ))
+        } else if let Some(trimesh2) = g2.as_shape::<TriMesh>() {
+            Ok(time_of_impact_shape_composite_shape(
+                &DefaultQueryDispatcher,
+                pos12,
+                vel12,
+                g1,
+                trimesh2,
+                max_toi,
+            ))
+        } else {
+            Err(Unsupported)
+        }
+    }
+
+    fn nonlinear_time_of_impact(
+        &self,
+        motion1: &NonlinearRigidMotion,
+        g1: &dyn Shape,
+        motion2: &NonlinearRigidMotion,
+        g2: &dyn Shape,
+        start_time: Real,
+        end_time: Real,
+        stop_at_penetration: bool,
+    ) -> Result<Option<TOI>, Unsupported> {
+        if let Some(trimesh1) = g1.as_shape::<TriMesh>() {
+            Ok(nonlinear_time_of_impact_composite_shape_shape(
+                &DefaultQueryDispatcher,
+                motion1,
+                trimesh1,
+                motion2,
+                g2,
+                start_time,
+                end_time,
+                stop_at_penetration,
+            ))
+        } else if let Some(trimesh2) = g2.as_shape::<TriMesh>() {
+            Ok(nonlinear_time_of_impact_shape_composite_shape(
+                &DefaultQueryDispatcher,
+                motion1,
+                g1,
+                motion2,
+                trimesh2,
+                start_time,
+                end_time,
+                stop_at_penetration,
+            ))
+        } else {
+            Err(Unsupported)
+        }
+    }
+}
+
+impl<ManifoldData, ContactData> PersistentQueryDispatcher<ManifoldData, ContactData>
+    for TriMeshDispatcher
+where
+    ManifoldData: Default + Clone,
+    ContactData: Default + Copy,
+{
+    fn contact_manifolds(
+        &self,
+        pos12: &Isometry<Real>,
+        g1: &dyn Shape,
+        g2: &dyn Shape,
+        prediction: Real,
+        manifolds: &mut Vec<ContactManifold<ManifoldData, ContactData>>,
+        workspace: &mut Option<ContactManifoldsWorkspace>,
+    ) -> Result<(), Unsupported> {
+        // The triangles themselves are plain convex shapes so the default dispatcher takes it
+        // from there.
+        let dispatcher = &DefaultQueryDispatcher;
+        // `TriMesh` is a composite shape, which is all the default dispatcher needs to know.
+        match (g1.as_shape::<TriMesh>(), g2.as_shape::<TriMesh>()) {
+            (Some(trimesh1), _) => {
+                dispatcher.contact_manifolds(pos12, g1, g2, prediction, manifolds, workspace)?;
+                trimesh1.correct_contact_manifolds(pos12, manifolds, false);
+            }
+            (None, Some(trimesh2)) => {
+                dispatcher.contact_manifolds(pos12, g1, g2, prediction, manifolds, workspace)?;
+                trimesh2.correct_contact_manifolds(&pos12.inverse(), manifolds, true);
+            }
+            (None, None) => return Err(Unsupported),
+        }
+
+        Ok(())
+    }
+
+    fn contact_manifold_convex_convex(
+        &self,
+        _pos12: &Isometry<Real>,
+        _g1: &dyn Shape,
+        _g2: &dyn Shape,
+        _prediction: Real,
+        _manifold: &mut ContactManifold<ManifoldData, ContactData>,
+    ) -> Result<(), Unsupported> {
+        // A trimesh is never convex.
+        Err(Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: Real = 1.0e-5;
+
+    /// A closed, outward facing unit cube centered on the origin.
+    fn cube() -> (Vec<Point<Real>>, Vec<[u32; 3]>) {
+        let vertices = vec![
+            Point::new(-0.5, -0.5, -0.5),
+            Point::new(0.5, -0.5, -0.5),
+            Point::new(0.5, 0.5, -0.5),
+            Point::new(-0.5, 0.5, -0.5),
+            Point::new(-0.5, -0.5, 0.5),
+            Point::new(0.5, -0.5, 0.5),
+            Point::new(0.5, 0.5, 0.5),
+            Point::new(-0.5, 0.5, 0.5),
+        ];
+        let indices = vec![
+            [0, 2, 1],
+            [0, 3, 2],
+            [4, 5, 6],
+            [4, 6, 7],
+            [0, 1, 5],
+            [0, 5, 4],
+            [3, 6, 2],
+            [3, 7, 6],
+            [0, 4, 7],
+            [0, 7, 3],
+            [1, 2, 6],
+            [1, 6, 5],
+        ];
+        (vertices, indices)
+    }
+
+    fn meshes() -> (TriMesh, rapier3d::geometry::TriMesh) {
+        let (vertices, indices) = cube();
+        let soup = indices
+            .iter()
+            .flat_map(|triangle| triangle.iter().map(|i| vertices[*i as usize]))
+            .collect::<Vec<_>>();
+        (
+            TriMesh::new(soup),
+            rapier3d::geometry::TriMesh::new(vertices, indices),
+        )
+    }
+
+    fn points() -> Vec<Point<Real>> {
+        vec![
+            Point::new(0., 0., 0.),
+            Point::new(0.1, -0.2, 0.3),
+            Point::new(2., 0., 0.),
+            Point::new(0.3, 4., -0.2),
+            Point::new(-1., -1., -1.),
+            Point::new(0.5, 0.5, 3.),
+        ]
+    }
+
+    fn rays() -> Vec<Ray> {
+        vec![
+            Ray::new(Point::new(0., 0., 5.), -Vector::z()),
+            Ray::new(Point::new(5., 0.1, 0.2), -Vector::x()),
+            Ray::new(Point::new(0.2, -5., -0.1), Vector::y()),
+            Ray::new(
+                Point::new(3., 3., 3.),
+                Vector::new(-1., -1., -1.).normalize(),
+            ),
+            Ray::new(Point::new(0.1, 0.2, 0.), Vector::x()),
+            Ray::new(Point::new(-0.1, 0., 0.2), -Vector::y()),
+        ]
+    }
+
+    fn assert_points_eq(a: &Point<Real>, b: &Point<Real>) {
+        assert!((a - b).norm() < EPSILON, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn ray_casts_match_builtin() {
+        let (custom, builtin) = meshes();
+        for ray in rays() {
+            for &solid in &[true, false] {
+                let expected = builtin.cast_local_ray_and_get_normal(&ray, Real::MAX, solid);
+                let actual = custom.cast_local_ray_and_get_normal(&ray, Real::MAX, solid);
+                match (expected, actual) {
+                    (Some(expected), Some(actual)) => {
+                        assert!((expected.toi - actual.toi).abs() < EPSILON);
+                        assert!((expected.normal - actual.normal).norm() < EPSILON);
+                        assert_eq!(expected.feature, actual.feature);
+                    }
+                    (None, None) => {}
+                    (expected, actual) => panic!("{:?} != {:?}", expected, actual),
+                }
+                assert_eq!(
+                    builtin.cast_local_ray(&ray, Real::MAX, solid).is_some(),
+                    custom.cast_local_ray(&ray, Real::MAX, solid).is_some()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn backfaces_are_detected() {
+        let (custom, builtin) = meshes();
+        // Starting inside a hollow mesh can only ever hit a backface.
+        let ray = Ray::new(Point::origin(), Vector::x());
+        let expected = builtin
+            .cast_local_ray_and_get_normal(&ray, Real::MAX, false)
+            .unwrap();
+        let actual = custom
+            .cast_local_ray_and_get_normal(&ray, Real::MAX, false)
+            .unwrap();
+        assert!(builtin.is_backface(expected.feature));
+        assert!(custom.is_backface(actual.feature));
+        assert_eq!(expected.feature, actual.feature);
+
+        let ray = Ray::new(Point::new(5., 0., 0.), -Vector::x());
+        let actual = custom
+            .cast_local_ray_and_get_normal(&ray, Real::MAX, false)
+            .unwrap();
+        assert!(!custom.is_backface(actual.feature));
+        assert!(!custom.is_backface(FeatureId::Vertex(100)));
+    }
+
+    #[test]
+    fn point_projections_match_builtin() {
+        let (custom, builtin) = meshes();
+        for point in points() {
+            for &solid in &[true, false] {
+                let expected = builtin.project_local_point(&point, solid);
+                let actual = custom.project_local_point(&point, solid);
+                assert_eq!(expected.is_inside, actual.is_inside);
+                assert_points_eq(&expected.point, &actual.point);
+
+                let expected = builtin.distance_to_local_point(&point, solid);
+                let actual = custom.distance_to_local_point(&point, solid);
+                assert!((expected - actual).abs() < EPSILON);
+            }
+
+            let (expected, expected_feature) = builtin.project_local_point_and_get_feature(&point);
+            let (actual, actual_feature) = custom.project_local_point_and_get_feature(&point);
+            assert_points_eq(&expected.point, &actual.point);
+            assert_eq!(expected_feature, actual_feature);
+
+            assert_eq!(
+                builtin.contains_local_point(&point),
+                custom.contains_local_point(&point)
+            );
+        }
+    }
+
+    #[test]
+    fn point_locations_match_builtin() {
+        let (custom, builtin) = meshes();
+        for point in points() {
+            let (expected, (expected_id, _)) =
+                builtin.project_local_point_and_get_location(&point, false);
+            let (actual, (actual_id, _)) =
+                custom.project_local_point_and_get_location(&point, false);
+            assert_points_eq(&expected.point, &actual.point);
+            assert_eq!(expected_id, actual_id);
+        }
+    }
+
+    #[test]
+    fn closed_meshes_are_fully_connected() {
+        let (custom, _) = meshes();
+        assert!(custom.topology().is_closed());
+
+        let open = TriMesh::new(custom.vertices()[3..].to_vec());
+        assert!(!open.topology().is_closed());
+    }
+
+    #[test]
+    fn internal_edge_normals_are_corrected() {
+        // Two coplanar triangles forming a floor, sharing the edge going from (1, 0, 0) to
+        // (0, 0, 1).
+        let floor = TriMesh::new(vec![
+            Point::new(0., 0., 0.),
+            Point::new(0., 0., 1.),
+            Point::new(1., 0., 0.),
+            Point::new(1., 0., 0.),
+            Point::new(0., 0., 1.),
+            Point::new(1., 0., 1.),
+        ]);
+        let up = Vector::y();
+        let snagged = Vector::new(-1., 1., -1.).normalize();
+        let on_edge = Point::new(0.51, 0., 0.51);
+        assert_eq!(floor.correct_contact_normal(0, &on_edge, &snagged), up);
+        assert_eq!(floor.correct_contact_normal(0, &on_edge, &up), up);
+
+        // The outer edges have no neighbour so anything goes.
+        let on_boundary = Point::new(-0.01, 0., 0.5);
+        let outward = Vector::new(-1., 1., 0.).normalize();
+        assert_eq!(
+            floor.correct_contact_normal(0, &on_boundary, &outward),
+            outward
+        );
+    }
+
+    #[test]
+    fn closed_meshes_contain_points() {
+        let (custom, _) = meshes();
+        assert!(custom.contains_point(&Point::origin()));
+        assert!(custom.contains_point(&Point::new(0.4, -0.4, 0.4)));
+        // Closest to a face, an edge and a corner respectively.
+        assert!(!custom.contains_point(&Point::new(0., 0.6, 0.)));
+        assert!(!custom.contains_point(&Point::new(0.6, 0.6, 0.)));
+        assert!(!custom.contains_point(&Point::new(0.6, 0.6, 0.6)));
+    }
+
+    #[test]
+    fn closed_meshes_have_mass() {
+        let (custom, _) = meshes();
+        let (volume, com, _) = custom.volume_properties().unwrap();
+        assert!((volume - 1.).abs() < EPSILON);
+        assert_points_eq(&com, &Point::origin());
+
+        // A solid cube of side 1 has an inertia of m / 6 around every axis, parry stores the
+        // inverse square root of it.
+        let inv_inertia_sqrt = Vector::repeat((6f32 / 2.).sqrt());
+        let props = custom.mass_properties(2.);
+        assert!((props.inv_mass - 0.5).abs() < EPSILON);
+        assert_points_eq(&props.local_com, &Point::origin());
+        assert!((props.inv_principal_inertia_sqrt - inv_inertia_sqrt).norm() < EPSILON);
+
+        let shifted = TriMesh::new(
+            custom
+                .vertices()
+                .iter()
+                .map(|vertex| vertex + Vector::new(3., -2., 1.))
+                .collect(),
+        );
+        let (volume, com, _) = shifted.volume_properties().unwrap();
+        assert!((volume - 1.).abs() < EPSILON);
+        assert_points_eq(&com, &Point::new(3., -2., 1.));
+        // The second moments about the origin are around m * |com|^2 = 28 here while the inertia
+        // about the center of mass is m / 6, so moving it there leaves the f32 rounding of the
+        // former (a few 1e-5 once every triangle is summed) in the result.
+        let props = shifted.mass_properties(2.);
+        assert!((props.inv_principal_inertia_sqrt - inv_inertia_sqrt).norm() < 1.0e-4);
+    }
+
+    #[test]
+    fn open_meshes_are_massless() {
+        let (custom, _) = meshes();
+        let open = TriMesh::new(custom.vertices()[3..].to_vec());
+        assert!(open.volume_properties().is_none());
+        assert_eq!(open.mass_properties(1.).inv_mass, 0.);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::entity::EntityKind;
+use crate::explosions::Explosion;
+use crate::gravity::{GravityField, GravityMode, GravityZone};
+use crate::lifetimes::{LifetimeRules, Lifetimes};
+use crate::physics::PhysicsContext;
+use crate::platforms::{Path, Platforms};
+use crate::spawner::{SpawnerId, Spawners};
+use rapier3d::dynamics::{RigidBody, RigidBodyBuilder};
+use rapier3d::geometry::{Collider, ColliderBuilder, ColliderHandle};
+use rapier3d::na::{Point3, Vector3};
+
+const LINK_HALF_LENGTH: f32 = 0.5;
+const LINK_RADIUS: f32 = 0.15;
+// space between neighbouring links so they don't collide with each other
+const LINK_GAP: f32 = 0.05;
+const WRECKING_BALL_RADIUS: f32 = 1.5;
+const WRECKING_BALL_DENSITY: f32 = 5.;
+const PLANETOID_GRAVITY: f32 = 9.81;
+
+/// The simulated state that timed callbacks are allowed to touch.
+pub struct World {
+    pub physics: PhysicsContext,
+    /// Everything the spawners have added.
+    pub spawned: Lifetimes,
+    /// Pieces split off the terrain and rubble thrown out by explosions, kept apart from
+    /// spawned bodies so they don't count towards or recycle those.
+    pub debris: Lifetimes,
+    pub spawners: Spawners,
+    pub platforms: Platforms,
+    // set off by the game, which owns the terrain, before the next physics tick
+    explosions: Vec<Explosion>,
+}
+
+impl World {
+    pub fn new(
+        physics: PhysicsContext,
+        spawn_rules: LifetimeRules,
+        debris_rules: LifetimeRules,
+    ) -> Self {
+        Self {
+            physics,
+            spawned: Lifetimes::new(spawn_rules),
+            debris: Lifetimes::new(debris_rules),
+            spawners: Spawners::default(),
+            platforms: Platforms::default(),
+            explosions: vec![],
+        }
+    }
+
+    /// Queues up an explosion to go off before the next physics tick.
+    pub fn explode(&mut self, explosion: Explosion) {
+        self.explosions.push(explosion);
+    }
+
+    pub fn drain_explosions(&mut self) -> std::vec::Drain<'_, Explosion> {
+        self.explosions.drain(..)
+    }
+
+    /// Adds a body subject to the spawn lifetime rules.
+    pub fn spawn(&mut self, body: RigidBody, collider: Collider) -> ColliderHandle {
+        let handle = self.physics.add_body(body, collider);
+        self.spawned.track(&mut self.physics, handle);
+        handle
+    }
+
+    /// Spawns one burst from the spawner `id`. Does nothing if it has been removed.
+    pub fn run_spawner(&mut self, id: SpawnerId) {
+        for (body, collider) in self.spawners.burst(id) {
+            self.spawn(body, collider);
+        }
+    }
+
+    /// Hangs a chain of `links` links from `anchor` with a heavy ball at the end. The chain
+    /// starts out stretched along +x so it swings down.
+    pub fn add_wrecking_ball(&mut self, anchor: Point3<f32>, links: usize) -> ColliderHandle {
+        let link_length = LINK_HALF_LENGTH * 2.;
+        let mut previous = None;
+        let mut previous_anchor = anchor;
+        for i in 0..links {
+            let center = anchor + Vector3::x() * (link_length * i as f32 + LINK_HALF_LENGTH);
+            let body = RigidBodyBuilder::new_dynamic()
+                .translation(center.x, center.y, center.z)
+                .build();
+            let collider =
+                ColliderBuilder::capsule_x(LINK_HALF_LENGTH - LINK_RADIUS - LINK_GAP, LINK_RADIUS)
+                    .user_data(EntityKind::Prop.user_data())
+                    .build();
+            let link = self.physics.add_body(body, collider);
+            self.physics.add_ball_joint(
+                previous,
+                previous_anchor,
+                Some(link),
+                Point3::new(-LINK_HALF_LENGTH, 0., 0.),
+            );
+            previous = Some(link);
+            previous_anchor = Point3::new(LINK_HALF_LENGTH, 0., 0.);
+        }
+
+        let offset = WRECKING_BALL_RADIUS + LINK_GAP;
+        let center = anchor + Vector3::x() * (link_length * links as f32 + offset);
+        let body = RigidBodyBuilder::new_dynamic()
+            .translation(center.x, center.y, center.z)
+            .build();
+        let collider = ColliderBuilder::ball(WRECKING_BALL_RADIUS)
+            .density(WRECKING_BALL_DENSITY)
+            .user_data(EntityKind::Prop.user_data())
+            .build();
+        let ball = self.physics.add_body(body, collider);
+        self.physics.add_ball_joint(
+            previous,
+            previous_anchor,
+            Some(ball),
+            Point3::new(-offset, 0., 0.),
+        );
+        ball
+    }
+
+    /// Adds a door hinged to the world along its left edge. `hinge` is the middle of that edge
+    /// and the door extends along +x from it.
+    pub fn add_door(&mut self, hinge: Point3<f32>, half_extents: Vector3<f32>) -> ColliderHandle {
+        let center = hinge + Vector3::x() * half_extents.x;
+        let body = RigidBodyBuilder::new_dynamic()
+            .translation(center.x, center.y, center.z)
+            .build();
+        let collider = ColliderBuilder::cuboid(half_extents.x, half_extents.y, half_extents.z)
+            .user_data(EntityKind::Prop.user_data())
+            .build();
+        let door = self.physics.add_body(body, collider);
+        self.physics.add_revolute_joint(
+            None,
+            hinge,
+            Vector3::y_axis(),
+            Some(door),
+            Point3::new(-half_extents.x, 0., 0.),
+            Vector3::y_axis(),
+        );
+        door
+    }
+
+    /// Adds a static ball that pulls everything within `reach` of its surface towards it
+    /// instead of down.
+    pub fn add_planetoid(
+        &mut self,
+        center: Point3<f32>,
+        radius: f32,
+        reach: f32,
+    ) -> ColliderHandle {
+        let body = RigidBodyBuilder::new_static()
+            .translation(center.x, center.y, center.z)
+            .build();
+        let collider = ColliderBuilder::ball(radius)
+            .user_data(EntityKind::Prop.user_data())
+            .build();
+        let planetoid = self.physics.add_body(body, collider);
+        self.physics.add_gravity_zone(GravityZone {
+            field: GravityField::Radial {
+                center,
+                radius: radius + reach,
+                strength: PLANETOID_GRAVITY,
+            },
+            mode: GravityMode::Override,
+        });
+        planetoid
+    }
+
+    /// Adds a box that moves along `path` and carries whatever is on top of it.
+    pub fn add_platform(&mut self, path: Path, half_extents: Vector3<f32>) -> ColliderHandle {
+        let start = path.position_at(0.);
+        let body = RigidBodyBuilder::new_kinematic()
+            .translation(start.x, start.y, start.z)
+            .build();
+        let collider = ColliderBuilder::cuboid(half_extents.x, half_extents.y, half_extents.z)
+            .user_data(EntityKind::Prop.user_data())
+            .build();
+        let platform = self.physics.add_body(body, collider);
+        self.platforms.insert(platform, path);
+        platform
+    }
+
+    /// Runs one physics tick of `dt` seconds and applies the lifetime rules.
+    pub fn step(&mut self, dt: f32) {
+        self.physics.set_timestep(dt);
+        self.platforms.update(&mut self.physics, dt);
+        self.physics.step();
+        self.spawned.update(&mut self.physics, dt);
+        self.debris.update(&mut self.physics, dt);
+    }
+}